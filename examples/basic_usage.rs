@@ -1,6 +1,6 @@
 use meta_rest::{
-    Field, Filter, InMemoryStorage, Resource, ResourceDefinition, ResourceManager, SecurityPolicy,
-    ValidationRule,
+    CallerContext, Field, Filter, FilterOperator, Format, InMemoryStorage, Resource,
+    ResourceDefinition, ResourceManager, SecurityPolicy, ValidationRule,
 };
 use std::collections::HashMap;
 
@@ -14,34 +14,43 @@ fn main() {
             Field {
                 name: "name".to_string(),
                 field_type: "string".to_string(),
-                required: true,
+                required: Some(true),
                 validation: Some(ValidationRule {
                     min: Some(3.0),
                     max: Some(50.0),
-                    pattern: None,
+                    ..Default::default()
                 }),
+                ..Default::default()
             },
             Field {
                 name: "age".to_string(),
                 field_type: "number".to_string(),
-                required: false,
+                required: Some(false),
                 validation: Some(ValidationRule {
                     min: Some(0.0),
                     max: Some(150.0),
-                    pattern: None,
+                    ..Default::default()
                 }),
+                ..Default::default()
             },
             Field {
                 name: "email".to_string(),
                 field_type: "string".to_string(),
-                required: true,
-                validation: None,
+                required: Some(true),
+                validation: Some(ValidationRule {
+                    format: Some(Format::Email),
+                    ..Default::default()
+                }),
+                ..Default::default()
             },
         ],
         security: Some(SecurityPolicy {
             require_auth: true,
             allowed_roles: Some(vec!["admin".to_string(), "user".to_string()]),
+            read: None,
+            write: None,
         }),
+        ..Default::default()
     };
 
     // Serialize definition to JSON
@@ -51,7 +60,10 @@ fn main() {
 
     // Create a resource manager with in-memory storage
     let storage = InMemoryStorage::new();
-    let mut manager = ResourceManager::new(user_definition, storage);
+    let mut manager = ResourceManager::new(user_definition, storage).unwrap();
+
+    // Operations are performed on behalf of a caller and checked against the security policy
+    let admin = CallerContext::authenticated("admin", &["admin"]);
 
     // POST - Create resources
     println!("Creating resources...");
@@ -59,29 +71,29 @@ fn main() {
     let user2 = create_user("2", "Bob Smith", 35.0, "bob@example.com");
     let user3 = create_user("3", "Charlie Brown", 42.0, "charlie@example.com");
 
-    manager.create(user1).unwrap();
-    manager.create(user2).unwrap();
-    manager.create(user3).unwrap();
+    manager.create(&admin, user1).unwrap();
+    manager.create(&admin, user2).unwrap();
+    manager.create(&admin, user3).unwrap();
     println!("Created 3 users\n");
 
     // GET - Retrieve a specific resource
     println!("Getting user with id '1':");
-    let user = manager.get("1").unwrap();
+    let user = manager.get(&admin, "1").unwrap();
     println!("{}\n", serde_json::to_string_pretty(&user).unwrap());
 
     // GET - List all resources
     println!("Listing all users:");
-    let all_users = manager.list().unwrap();
+    let all_users = manager.list(&admin).unwrap();
     println!("Found {} users\n", all_users.len());
 
     // GET - Filter resources
     println!("Filtering users with age > 30:");
     let filters = vec![Filter {
         field: "age".to_string(),
-        operator: "gt".to_string(),
+        operator: FilterOperator::Gt,
         value: serde_json::Value::Number(serde_json::Number::from_f64(30.0).unwrap()),
     }];
-    let filtered = manager.list_filtered(&filters).unwrap();
+    let filtered = manager.list_filtered(&admin, &filters).unwrap();
     for user in &filtered {
         println!(
             "  - {} (age: {})",
@@ -94,8 +106,8 @@ fn main() {
     // PUT - Update a resource
     println!("Updating user '1'...");
     let updated_user = create_user("1", "Alice Johnson-Smith", 29.0, "alice.smith@example.com");
-    manager.update("1", updated_user).unwrap();
-    let user = manager.get("1").unwrap();
+    manager.update(&admin, "1", updated_user).unwrap();
+    let user = manager.get(&admin, "1").unwrap();
     println!(
         "Updated: {}\n",
         serde_json::to_string_pretty(&user).unwrap()
@@ -103,18 +115,25 @@ fn main() {
 
     // DELETE - Remove a resource
     println!("Deleting user '3'...");
-    manager.delete("3").unwrap();
-    let all_users = manager.list().unwrap();
+    manager.delete(&admin, "3").unwrap();
+    let all_users = manager.list(&admin).unwrap();
     println!("Remaining users: {}\n", all_users.len());
 
     // Validation example - this will fail
     println!("Testing validation (this should fail):");
     let invalid_user = create_user("4", "Jo", 200.0, "jo@example.com"); // Name too short, age too high
-    match manager.create(invalid_user) {
+    match manager.create(&admin, invalid_user) {
         Ok(_) => println!("Unexpected success"),
         Err(e) => println!("Validation error (expected): {}\n", e),
     }
 
+    // Security example - this will fail
+    println!("Testing security (this should fail):");
+    match manager.list(&CallerContext::anonymous()) {
+        Ok(_) => println!("Unexpected success"),
+        Err(e) => println!("Security error (expected): {}\n", e),
+    }
+
     println!("=== Example completed successfully! ===");
 }
 
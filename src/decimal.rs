@@ -0,0 +1,193 @@
+//! Exact decimal arithmetic on JSON numbers
+//!
+//! JSON numbers are compared and checked against `integer`/`decimal` rules through their
+//! decimal representation, so that `0.3` is a multiple of `0.1` and large integers keep
+//! every digit. Numbers that do not fit fall back to floating point in comparisons.
+
+use std::cmp::Ordering;
+
+/// Decimal number as `mantissa * 10^-scale`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Decimal {
+    mantissa: i128,
+    scale: u32,
+}
+
+impl Decimal {
+    /// Decimal value of a JSON number, exact for integers and for floating point numbers
+    /// as written in their shortest representation
+    pub(crate) fn from_number(number: &serde_json::Number) -> Option<Self> {
+        if let Some(n) = number.as_i64() {
+            return Some(Self::integer(n.into()));
+        }
+        if let Some(n) = number.as_u64() {
+            return Some(Self::integer(n.into()));
+        }
+        Self::parse(&number.to_string())
+    }
+
+    /// Decimal value of a floating point number, as written in its shortest representation
+    pub(crate) fn from_f64(value: f64) -> Option<Self> {
+        serde_json::Number::from_f64(value).and_then(|n| Self::from_number(&n))
+    }
+
+    fn integer(mantissa: i128) -> Self {
+        Self { mantissa, scale: 0 }
+    }
+
+    /// Parse `[-]digits[.digits][e[+|-]digits]`
+    pub(crate) fn parse(text: &str) -> Option<Self> {
+        let (number, exponent) = match text.find(['e', 'E']) {
+            Some(at) => (&text[..at], text[at + 1..].parse::<i32>().ok()?),
+            None => (text, 0),
+        };
+        let (negative, number) = match number.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, number),
+        };
+        let (whole, fraction) = number.split_once('.').unwrap_or((number, ""));
+        if whole.is_empty() || !(whole.bytes().chain(fraction.bytes())).all(|b| b.is_ascii_digit())
+        {
+            return None;
+        }
+
+        let mut mantissa: i128 = 0;
+        for digit in whole.bytes().chain(fraction.bytes()) {
+            mantissa = mantissa
+                .checked_mul(10)?
+                .checked_add(i128::from(digit - b'0'))?;
+        }
+        if negative {
+            mantissa = -mantissa;
+        }
+
+        let scale = i64::try_from(fraction.len()).ok()? - i64::from(exponent);
+        if scale >= 0 {
+            Some(Self {
+                mantissa,
+                scale: u32::try_from(scale).ok()?,
+            })
+        } else {
+            let factor = 10i128.checked_pow(u32::try_from(-scale).ok()?)?;
+            Some(Self::integer(mantissa.checked_mul(factor)?))
+        }
+    }
+
+    /// The same value without trailing fractional zeros
+    fn normalized(self) -> Self {
+        let mut decimal = self;
+        while decimal.scale > 0 && decimal.mantissa % 10 == 0 {
+            decimal.mantissa /= 10;
+            decimal.scale -= 1;
+        }
+        decimal
+    }
+
+    /// Number of significant fractional digits
+    pub(crate) fn scale(&self) -> u32 {
+        self.normalized().scale
+    }
+
+    /// Number of digits before the decimal point, zero for values below one
+    pub(crate) fn integer_digits(&self) -> u32 {
+        let whole = self.mantissa.unsigned_abs() / 10u128.pow(self.scale.min(38));
+        match whole {
+            0 => 0,
+            _ => whole.ilog10() + 1,
+        }
+    }
+
+    /// Mantissas of both values at a common scale
+    fn aligned(&self, other: &Self) -> Option<(i128, i128)> {
+        let scale = self.scale.max(other.scale);
+        let widen = |d: &Self| {
+            10i128
+                .checked_pow(scale - d.scale)
+                .and_then(|factor| d.mantissa.checked_mul(factor))
+        };
+        Some((widen(self)?, widen(other)?))
+    }
+
+    /// Exact comparison, `None` if the values cannot be aligned
+    pub(crate) fn compare(&self, other: &Self) -> Option<Ordering> {
+        self.aligned(other).map(|(a, b)| a.cmp(&b))
+    }
+
+    /// Whether the value is an integer multiple of `other`
+    pub(crate) fn is_multiple_of(&self, other: &Self) -> Option<bool> {
+        let (a, b) = self.aligned(other)?;
+        match b {
+            0 => None,
+            _ => Some(a % b == 0),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decimal(text: &str) -> Decimal {
+        Decimal::parse(text).unwrap()
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            decimal("12.50"),
+            Decimal {
+                mantissa: 1250,
+                scale: 2
+            }
+        );
+        assert_eq!(
+            decimal("-0.5"),
+            Decimal {
+                mantissa: -5,
+                scale: 1
+            }
+        );
+        assert_eq!(
+            decimal("1.5e3"),
+            Decimal {
+                mantissa: 1500,
+                scale: 0
+            }
+        );
+        assert_eq!(
+            decimal("2e-3"),
+            Decimal {
+                mantissa: 2,
+                scale: 3
+            }
+        );
+        assert_eq!(Decimal::parse("1."), Some(decimal("1")));
+        assert_eq!(Decimal::parse("abc"), None);
+        assert_eq!(Decimal::parse(".5"), None);
+        assert_eq!(Decimal::parse("1e400"), None);
+    }
+
+    #[test]
+    fn test_exact_arithmetic() {
+        assert_eq!(decimal("12.50").scale(), 1);
+        assert_eq!(decimal("12.50").integer_digits(), 2);
+        assert_eq!(decimal("0.05").integer_digits(), 0);
+        assert_eq!(decimal("0.3").is_multiple_of(&decimal("0.1")), Some(true));
+        assert_eq!(decimal("0.35").is_multiple_of(&decimal("0.1")), Some(false));
+        assert_eq!(decimal("1").is_multiple_of(&decimal("0")), None);
+        assert_eq!(
+            decimal("0.1").compare(&decimal("0.10")),
+            Some(Ordering::Equal)
+        );
+
+        // Adjacent integers that are the same as f64
+        let larger = serde_json::Number::from(9_007_199_254_740_993u64);
+        let smaller = serde_json::Number::from(9_007_199_254_740_992u64);
+        let (larger, smaller) = (
+            Decimal::from_number(&larger).unwrap(),
+            Decimal::from_number(&smaller).unwrap(),
+        );
+        assert_eq!(larger.compare(&smaller), Some(Ordering::Greater));
+        assert_eq!(Decimal::from_f64(0.1), Some(decimal("0.1")));
+    }
+}
@@ -0,0 +1,251 @@
+//! Default values of fields
+//!
+//! A field can declare a literal `default` or a `generate` strategy. Missing fields are
+//! filled in by [`ResourceManager::create`](crate::ResourceManager::create) before the
+//! resource is validated and stored, including fields of objects nested in arrays. Item
+//! schemas themselves take neither, since array elements are never missing.
+
+use crate::format::{format_date, format_date_time};
+use crate::validation::{validate_field, ValidationErrors};
+use crate::{Field, MetaRestError, TypeRegistry, ValidatorRegistry};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Strategy for generating a field value on creation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Generator {
+    /// Current time as a UTC `date-time`
+    Now,
+    /// Current UTC `date`
+    Today,
+    /// Random (version 4) UUID
+    Uuid,
+}
+
+impl Generator {
+    /// Produce a new value
+    pub fn generate(&self) -> serde_json::Value {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        let seconds = now.as_secs() as i64;
+        match self {
+            Generator::Now => format_date_time(seconds, now.subsec_nanos()).into(),
+            Generator::Today => format_date(seconds.div_euclid(86_400)).into(),
+            Generator::Uuid => uuid::Uuid::new_v4().to_string().into(),
+        }
+    }
+}
+
+/// Value to fill in for a missing field
+fn default_value(field: &Field) -> Option<serde_json::Value> {
+    match (&field.default, field.generate) {
+        (Some(value), _) => Some(value.clone()),
+        (None, Some(generator)) => Some(generator.generate()),
+        (None, None) => None,
+    }
+}
+
+/// Fill in missing top-level fields, and missing fields of nested objects and array items
+/// that are present
+pub(crate) fn apply_defaults(fields: &[Field], data: &mut HashMap<String, serde_json::Value>) {
+    for field in fields {
+        if !data.contains_key(&field.name) {
+            if let Some(value) = default_value(field) {
+                data.insert(field.name.clone(), value);
+            }
+        }
+        if let Some(value) = data.get_mut(&field.name) {
+            apply_nested_defaults(field, value);
+        }
+    }
+}
+
+fn apply_nested_defaults(field: &Field, value: &mut serde_json::Value) {
+    if let (Some(nested), Some(object)) = (&field.fields, value.as_object_mut()) {
+        for field in nested {
+            if !object.contains_key(&field.name) {
+                if let Some(value) = default_value(field) {
+                    object.insert(field.name.clone(), value);
+                }
+            }
+            if let Some(value) = object.get_mut(&field.name) {
+                apply_nested_defaults(field, value);
+            }
+        }
+    }
+    if let (Some(items), Some(array)) = (&field.items, value.as_array_mut()) {
+        for item in array {
+            apply_nested_defaults(items, item);
+        }
+    }
+}
+
+/// Check that defaults and generated values are valid values of their fields
+pub(crate) fn check_defaults(
+    fields: &[Field],
+    prefix: &str,
+    patterns: &HashMap<String, Regex>,
+    validators: &ValidatorRegistry,
+    types: &TypeRegistry,
+) -> Result<(), MetaRestError> {
+    for field in fields {
+        let path = match prefix {
+            "" => field.name.clone(),
+            _ => format!("{}.{}", prefix, field.name),
+        };
+        check_field(field, &path, patterns, validators, types)?;
+    }
+    Ok(())
+}
+
+fn check_field(
+    field: &Field,
+    path: &str,
+    patterns: &HashMap<String, Regex>,
+    validators: &ValidatorRegistry,
+    types: &TypeRegistry,
+) -> Result<(), MetaRestError> {
+    if field.default.is_some() && field.generate.is_some() {
+        return Err(MetaRestError::InvalidDefinition(format!(
+            "Field '{}' has both a default and a generator",
+            path
+        )));
+    }
+    if field.generate.is_some() && field.field_type != "string" {
+        return Err(MetaRestError::InvalidDefinition(format!(
+            "Field '{}' of type '{}' cannot be generated, generators produce strings",
+            path, field.field_type
+        )));
+    }
+    // A generated sample stands in for all generated values, which share their shape
+    let sample = match (&field.default, field.generate) {
+        (Some(default), _) => Some((default.clone(), "default")),
+        (None, Some(generator)) => Some((generator.generate(), "generator")),
+        (None, None) => None,
+    };
+    if let Some((value, kind)) = sample {
+        let mut errors = ValidationErrors::default();
+        validate_field(
+            field,
+            path,
+            &value,
+            patterns,
+            validators,
+            types,
+            &mut errors,
+        );
+        if !errors.is_empty() {
+            return Err(MetaRestError::InvalidDefinition(format!(
+                "Field '{}' has an invalid {}: {}",
+                path, kind, errors
+            )));
+        }
+    }
+    if let Some(nested) = &field.fields {
+        check_defaults(nested, path, patterns, validators, types)?;
+    }
+    if let Some(items) = &field.items {
+        if items.default.is_some() || items.generate.is_some() {
+            return Err(MetaRestError::InvalidDefinition(format!(
+                "Items of field '{}' cannot have a default or generator, since array \
+                 elements are never missing",
+                path
+            )));
+        }
+        check_field(items, &format!("{}[]", path), patterns, validators, types)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Format;
+
+    #[test]
+    fn test_generators() {
+        let now = Generator::Now.generate();
+        assert!(Format::DateTime.is_valid(now.as_str().unwrap()));
+        let today = Generator::Today.generate();
+        assert!(Format::Date.is_valid(today.as_str().unwrap()));
+
+        let (a, b) = (Generator::Uuid.generate(), Generator::Uuid.generate());
+        assert!(Format::Uuid.is_valid(a.as_str().unwrap()));
+        assert_eq!(&a.as_str().unwrap()[14..15], "4");
+        assert_ne!(a, b);
+    }
+
+    #[test]
+    fn test_defaults_in_array_items() {
+        let lines: Field = serde_json::from_value(serde_json::json!({
+            "name": "lines", "field_type": "array",
+            "items": { "field_type": "object", "fields": [
+                { "name": "sku", "field_type": "string" },
+                { "name": "quantity", "field_type": "integer", "default": 1 }
+            ] }
+        }))
+        .unwrap();
+        let mut data = HashMap::from([(
+            "lines".to_string(),
+            serde_json::json!([{ "sku": "a" }, { "sku": "b", "quantity": 3 }]),
+        )]);
+        apply_defaults(std::slice::from_ref(&lines), &mut data);
+        assert_eq!(
+            data["lines"],
+            serde_json::json!([{ "sku": "a", "quantity": 1 }, { "sku": "b", "quantity": 3 }])
+        );
+
+        let (validators, types) = (ValidatorRegistry::new(), TypeRegistry::new());
+        let mut invalid = lines.clone();
+        let items = invalid.items.as_mut().unwrap();
+        items.fields.as_mut().unwrap()[1].default = Some("one".into());
+        match check_defaults(&[invalid], "", &HashMap::new(), &validators, &types) {
+            Err(MetaRestError::InvalidDefinition(msg)) => {
+                assert!(msg.contains("'lines[].quantity' has an invalid default"))
+            }
+            _ => panic!("Expected InvalidDefinition error"),
+        }
+
+        let mut generated = lines;
+        generated.items.as_mut().unwrap().generate = Some(Generator::Uuid);
+        match check_defaults(&[generated], "", &HashMap::new(), &validators, &types) {
+            Err(MetaRestError::InvalidDefinition(msg)) => {
+                assert!(msg.contains("Items of field 'lines' cannot have a default or generator"))
+            }
+            _ => panic!("Expected InvalidDefinition error"),
+        }
+    }
+
+    #[test]
+    fn test_generators_must_fit_field() {
+        let check = |field: serde_json::Value| {
+            let field: Field = serde_json::from_value(field).unwrap();
+            let (validators, types) = (ValidatorRegistry::new(), TypeRegistry::new());
+            check_defaults(&[field], "", &HashMap::new(), &validators, &types)
+        };
+
+        assert!(check(serde_json::json!({
+            "name": "created_on", "field_type": "string", "generate": "today",
+            "validation": { "format": "date" }
+        }))
+        .is_ok());
+        match check(serde_json::json!({
+            "name": "created_on", "field_type": "string", "generate": "now",
+            "validation": { "format": "date" }
+        })) {
+            Err(MetaRestError::InvalidDefinition(msg)) => {
+                assert!(msg.contains("'created_on' has an invalid generator"))
+            }
+            _ => panic!("Expected InvalidDefinition error"),
+        }
+        assert!(check(serde_json::json!({
+            "name": "id", "field_type": "string", "generate": "uuid",
+            "allowed_values": ["a", "b"]
+        }))
+        .is_err());
+    }
+}
@@ -0,0 +1,766 @@
+//! Filters and boolean filter expressions
+//!
+//! A [`FilterExpr`] combines [`Filter`] leaves with `and`, `or` and `not` nodes. In JSON,
+//! groups are written as single-key objects and leaves as plain filters:
+//!
+//! ```json
+//! { "or": [
+//!     { "field": "age", "operator": "gt", "value": 60 },
+//!     { "and": [
+//!         { "field": "team", "operator": "eq", "value": "ops" },
+//!         { "not": { "field": "name", "operator": "contains", "value": "bot" } }
+//!     ] }
+//! ] }
+//! ```
+
+use crate::query::compare_values;
+use crate::types::Comparator;
+use crate::{MetaRestError, Resource, ResourceDefinition, RuleKind, ValidationErrors, Violation};
+use regex::Regex;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::borrow::Cow;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+/// Filter criteria for querying resources
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Filter {
+    /// Field name or dotted path of a nested field to filter on
+    pub field: String,
+    /// Filter operator
+    pub operator: FilterOperator,
+    /// Value to compare against
+    pub value: serde_json::Value,
+}
+
+/// Comparison performed by a filter
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FilterOperator {
+    /// Equal to the value
+    Eq,
+    /// Not equal to the value
+    Ne,
+    /// Greater than the value
+    Gt,
+    /// Greater than or equal to the value
+    Gte,
+    /// Less than the value
+    Lt,
+    /// Less than or equal to the value
+    Lte,
+    /// Equal to one of the values of an array
+    In,
+    /// Equal to none of the values of an array
+    Nin,
+    /// Within an inclusive `[low, high]` range
+    Between,
+    /// String starts with the value
+    StartsWith,
+    /// String ends with the value
+    EndsWith,
+    /// String contains the value, or array contains an element equal to it
+    Contains,
+    /// String contains the value, ignoring case
+    IContains,
+    /// String matches the regular expression
+    Regex,
+    /// Field is present (`true`) or absent (`false`)
+    Exists,
+    /// Field is null or absent; the value is ignored
+    IsNull,
+    /// Field is present and not null; the value is ignored
+    IsNotNull,
+}
+
+impl FilterOperator {
+    /// All operators
+    pub const ALL: [FilterOperator; 17] = [
+        FilterOperator::Eq,
+        FilterOperator::Ne,
+        FilterOperator::Gt,
+        FilterOperator::Gte,
+        FilterOperator::Lt,
+        FilterOperator::Lte,
+        FilterOperator::In,
+        FilterOperator::Nin,
+        FilterOperator::Between,
+        FilterOperator::StartsWith,
+        FilterOperator::EndsWith,
+        FilterOperator::Contains,
+        FilterOperator::IContains,
+        FilterOperator::Regex,
+        FilterOperator::Exists,
+        FilterOperator::IsNull,
+        FilterOperator::IsNotNull,
+    ];
+
+    /// Name of the operator in meta-descriptions and query strings
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FilterOperator::Eq => "eq",
+            FilterOperator::Ne => "ne",
+            FilterOperator::Gt => "gt",
+            FilterOperator::Gte => "gte",
+            FilterOperator::Lt => "lt",
+            FilterOperator::Lte => "lte",
+            FilterOperator::In => "in",
+            FilterOperator::Nin => "nin",
+            FilterOperator::Between => "between",
+            FilterOperator::StartsWith => "startsWith",
+            FilterOperator::EndsWith => "endsWith",
+            FilterOperator::Contains => "contains",
+            FilterOperator::IContains => "icontains",
+            FilterOperator::Regex => "regex",
+            FilterOperator::Exists => "exists",
+            FilterOperator::IsNull => "isNull",
+            FilterOperator::IsNotNull => "isNotNull",
+        }
+    }
+}
+
+impl fmt::Display for FilterOperator {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for FilterOperator {
+    type Err = MetaRestError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|op| op.as_str() == s)
+            .ok_or_else(|| {
+                let known: Vec<&str> = Self::ALL.iter().map(|op| op.as_str()).collect();
+                MetaRestError::ValidationError(
+                    Violation::new(
+                        "operator",
+                        RuleKind::Invalid,
+                        format!(
+                            "Unknown filter operator '{}', expected one of: {}",
+                            s,
+                            known.join(", ")
+                        ),
+                    )
+                    .into(),
+                )
+            })
+    }
+}
+
+impl Serialize for FilterOperator {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for FilterOperator {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        name.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+impl Filter {
+    /// Create a filter
+    pub fn new(
+        field: impl Into<String>,
+        operator: FilterOperator,
+        value: impl Into<serde_json::Value>,
+    ) -> Self {
+        Self {
+            field: field.into(),
+            operator,
+            value: value.into(),
+        }
+    }
+
+    /// Check that the value has the shape the operator expects
+    fn check(&self) -> Result<(), MetaRestError> {
+        let value = &self.value;
+        let (valid, expected) = match self.operator {
+            FilterOperator::Eq
+            | FilterOperator::Ne
+            | FilterOperator::Contains
+            | FilterOperator::IsNull
+            | FilterOperator::IsNotNull => (true, ""),
+            FilterOperator::Gt | FilterOperator::Gte | FilterOperator::Lt | FilterOperator::Lte => {
+                (value.is_number() || value.is_string(), "a number or string")
+            }
+            FilterOperator::In | FilterOperator::Nin => (value.is_array(), "an array"),
+            FilterOperator::Between => (
+                value.as_array().map(Vec::len) == Some(2),
+                "an array of two values",
+            ),
+            FilterOperator::StartsWith
+            | FilterOperator::EndsWith
+            | FilterOperator::IContains
+            | FilterOperator::Regex => (value.is_string(), "a string"),
+            FilterOperator::Exists => (value.is_boolean(), "a boolean"),
+        };
+
+        if valid {
+            return Ok(());
+        }
+        Err(MetaRestError::ValidationError(
+            Violation::new(
+                &self.field,
+                RuleKind::Invalid,
+                format!(
+                    "Filter operator '{}' on field '{}' expects {}",
+                    self.operator, self.field, expected
+                ),
+            )
+            .with_values(expected, value.clone())
+            .into(),
+        ))
+    }
+
+    /// Check the filter values against the allowed values of the field
+    ///
+    /// `eq`, `ne`, `in` and `nin` compare against the field's allowed values, `contains`
+    /// against the allowed values of its array items.
+    fn check_allowed(&self, definition: &ResourceDefinition) -> Result<(), Violation> {
+        let field = match definition.field(&self.field) {
+            Some(field) => field,
+            None => return Ok(()),
+        };
+        let (allowed, values) = match self.operator {
+            FilterOperator::Eq | FilterOperator::Ne => (
+                field.allowed_values.as_ref(),
+                std::slice::from_ref(&self.value),
+            ),
+            FilterOperator::In | FilterOperator::Nin => (
+                field.allowed_values.as_ref(),
+                self.value.as_array().map(Vec::as_slice).unwrap_or_default(),
+            ),
+            FilterOperator::Contains => (
+                field
+                    .items
+                    .as_ref()
+                    .and_then(|items| items.allowed_values.as_ref()),
+                std::slice::from_ref(&self.value),
+            ),
+            _ => return Ok(()),
+        };
+
+        let allowed = match allowed {
+            Some(allowed) => allowed,
+            None => return Ok(()),
+        };
+        match values
+            .iter()
+            .find(|value| !allowed.iter().any(|a| values_equal(a, value)))
+        {
+            Some(value) => Err(Violation::new(
+                &self.field,
+                RuleKind::Enum,
+                format!(
+                    "Filter value {} is not an allowed value of field '{}'",
+                    value, self.field
+                ),
+            )
+            .with_values(allowed.clone(), value.clone())),
+            None => Ok(()),
+        }
+    }
+
+    /// Check whether a resource matches the filter
+    ///
+    /// Missing fields never match, except for `exists: false` and `isNull`. Fails if the
+    /// value does not fit the operator.
+    pub fn matches(&self, resource: &Resource) -> Result<bool, MetaRestError> {
+        FilterExpr::Filter(self.clone())
+            .compile()
+            .map(|f| f.matches(resource))
+    }
+
+    fn evaluate(&self, resource: &Resource, context: &CompiledFilter) -> bool {
+        let value = match resource.value(&self.field) {
+            Some(value) => value,
+            None => {
+                return match self.operator {
+                    FilterOperator::Exists => self.value == false,
+                    FilterOperator::IsNull => true,
+                    _ => false,
+                }
+            }
+        };
+
+        // Fields of custom types with a comparison are compared by it, others by JSON value
+        let comparator = context.comparator(&self.field);
+        let order = |other: &serde_json::Value| match comparator {
+            Some(compare) => Some(compare(value, other)),
+            None if same_kind(value, other) => Some(compare_values(value, other)),
+            None => None,
+        };
+        let equal = |other: &serde_json::Value| match comparator {
+            Some(compare) => compare(value, other).is_eq(),
+            None => values_equal(value, other),
+        };
+        let in_values = || {
+            self.value
+                .as_array()
+                .is_some_and(|values| values.iter().any(equal))
+        };
+        let strings = || value.as_str().zip(self.value.as_str());
+
+        match self.operator {
+            FilterOperator::Eq => equal(&self.value),
+            FilterOperator::Ne => !equal(&self.value),
+            FilterOperator::Gt => order(&self.value) == Some(Ordering::Greater),
+            FilterOperator::Gte => order(&self.value).is_some_and(Ordering::is_ge),
+            FilterOperator::Lt => order(&self.value) == Some(Ordering::Less),
+            FilterOperator::Lte => order(&self.value).is_some_and(Ordering::is_le),
+            FilterOperator::In => in_values(),
+            FilterOperator::Nin => !in_values(),
+            FilterOperator::Between => match self.value.as_array().map(Vec::as_slice) {
+                Some([low, high]) => {
+                    order(low).is_some_and(Ordering::is_ge)
+                        && order(high).is_some_and(Ordering::is_le)
+                }
+                _ => false,
+            },
+            FilterOperator::StartsWith => strings().is_some_and(|(v, p)| v.starts_with(p)),
+            FilterOperator::EndsWith => strings().is_some_and(|(v, p)| v.ends_with(p)),
+            FilterOperator::Contains => match value {
+                serde_json::Value::Array(items) => {
+                    items.iter().any(|item| values_equal(item, &self.value))
+                }
+                _ => strings().is_some_and(|(v, p)| v.contains(p)),
+            },
+            FilterOperator::IContains => {
+                strings().is_some_and(|(v, p)| v.to_lowercase().contains(&p.to_lowercase()))
+            }
+            FilterOperator::Regex => match (value.as_str(), self.value.as_str()) {
+                (Some(v), Some(pattern)) => context
+                    .regexes
+                    .get(pattern)
+                    .is_some_and(|re| re.is_match(v)),
+                _ => false,
+            },
+            FilterOperator::Exists => self.value == true,
+            FilterOperator::IsNull => value.is_null(),
+            FilterOperator::IsNotNull => !value.is_null(),
+        }
+    }
+}
+
+/// Equality that treats numbers by value, so `30` equals `30.0`
+pub(crate) fn values_equal(a: &serde_json::Value, b: &serde_json::Value) -> bool {
+    match (a, b) {
+        (serde_json::Value::Number(_), serde_json::Value::Number(_)) => {
+            compare_values(a, b) == Ordering::Equal
+        }
+        _ => a == b,
+    }
+}
+
+/// Whether two values are both numbers or both strings, and thus ordered
+fn same_kind(a: &serde_json::Value, b: &serde_json::Value) -> bool {
+    (a.is_number() && b.is_number()) || (a.is_string() && b.is_string())
+}
+
+/// Check filters against a resource definition, reporting every offending filter
+pub(crate) fn check_filters<'a>(
+    filters: impl IntoIterator<Item = &'a Filter>,
+    definition: &ResourceDefinition,
+) -> Result<(), MetaRestError> {
+    let mut errors = ValidationErrors::default();
+    for filter in filters {
+        if let Err(violation) = filter.check_allowed(definition) {
+            errors.push(violation);
+        }
+    }
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(MetaRestError::ValidationError(errors))
+    }
+}
+
+/// Boolean expression over filters
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FilterExpr {
+    /// All sub-expressions must match; an empty group matches everything
+    And(Vec<FilterExpr>),
+    /// At least one sub-expression must match; an empty group matches nothing
+    Or(Vec<FilterExpr>),
+    /// The sub-expression must not match
+    Not(Box<FilterExpr>),
+    /// A single filter
+    #[serde(untagged)]
+    Filter(Filter),
+}
+
+impl FilterExpr {
+    /// Expression matching every resource
+    pub fn all() -> Self {
+        FilterExpr::And(Vec::new())
+    }
+
+    /// Combine with another expression so both must match
+    pub fn and(self, other: impl Into<FilterExpr>) -> Self {
+        match self {
+            FilterExpr::And(mut exprs) => {
+                exprs.push(other.into());
+                FilterExpr::And(exprs)
+            }
+            expr => FilterExpr::And(vec![expr, other.into()]),
+        }
+    }
+
+    /// Combine with another expression so either may match
+    pub fn or(self, other: impl Into<FilterExpr>) -> Self {
+        match self {
+            FilterExpr::Or(mut exprs) => {
+                exprs.push(other.into());
+                FilterExpr::Or(exprs)
+            }
+            expr => FilterExpr::Or(vec![expr, other.into()]),
+        }
+    }
+
+    /// Negate an expression
+    pub fn not(expr: impl Into<FilterExpr>) -> Self {
+        FilterExpr::Not(Box::new(expr.into()))
+    }
+
+    /// Check whether a resource matches the expression
+    ///
+    /// Fails if a filter value does not fit its operator. To evaluate an expression
+    /// against many resources, [`compile`](FilterExpr::compile) it once instead.
+    pub fn matches(&self, resource: &Resource) -> Result<bool, MetaRestError> {
+        self.compile().map(|compiled| compiled.matches(resource))
+    }
+
+    /// Check every filter and prepare the expression for evaluation
+    ///
+    /// Regular expressions are compiled once here rather than per resource.
+    pub fn compile(&self) -> Result<CompiledFilter<'_>, MetaRestError> {
+        self.compile_with(None)
+    }
+
+    /// Compile with the comparators of custom field types, by path
+    ///
+    /// Comparison filters on those fields take any value, which the comparator judges.
+    pub(crate) fn compile_with<'a>(
+        &'a self,
+        comparators: Option<&'a HashMap<String, Comparator>>,
+    ) -> Result<CompiledFilter<'a>, MetaRestError> {
+        Ok(CompiledFilter {
+            expr: self,
+            regexes: Cow::Owned(self.compile_regexes(comparators)?),
+            comparators,
+        })
+    }
+
+    /// Check every filter and compile the regular expressions of the expression, by pattern,
+    /// to keep them for [`compiled`](FilterExpr::compiled)
+    pub(crate) fn compile_regexes(
+        &self,
+        comparators: Option<&HashMap<String, Comparator>>,
+    ) -> Result<HashMap<String, Regex>, MetaRestError> {
+        let mut regexes = HashMap::new();
+        for filter in self.leaves() {
+            let custom = comparators.is_some_and(|c| c.contains_key(&filter.field));
+            let ordering = matches!(
+                filter.operator,
+                FilterOperator::Gt | FilterOperator::Gte | FilterOperator::Lt | FilterOperator::Lte
+            );
+            if !(custom && ordering) {
+                filter.check()?;
+            }
+            if filter.operator == FilterOperator::Regex {
+                let pattern = filter.value.as_str().unwrap_or_default();
+                let regex = Regex::new(pattern).map_err(|e| {
+                    MetaRestError::ValidationError(
+                        Violation::new(
+                            &filter.field,
+                            RuleKind::Pattern,
+                            format!("Invalid regular expression '{}': {}", pattern, e),
+                        )
+                        .into(),
+                    )
+                })?;
+                regexes.insert(pattern.to_string(), regex);
+            }
+        }
+        Ok(regexes)
+    }
+
+    /// The expression ready for evaluation with regular expressions compiled before by
+    /// [`compile_regexes`](FilterExpr::compile_regexes)
+    pub(crate) fn compiled<'a>(
+        &'a self,
+        regexes: &'a HashMap<String, Regex>,
+        comparators: Option<&'a HashMap<String, Comparator>>,
+    ) -> CompiledFilter<'a> {
+        CompiledFilter {
+            expr: self,
+            regexes: Cow::Borrowed(regexes),
+            comparators,
+        }
+    }
+
+    fn evaluate(&self, resource: &Resource, context: &CompiledFilter) -> bool {
+        match self {
+            FilterExpr::And(exprs) => exprs.iter().all(|expr| expr.evaluate(resource, context)),
+            FilterExpr::Or(exprs) => exprs.iter().any(|expr| expr.evaluate(resource, context)),
+            FilterExpr::Not(expr) => !expr.evaluate(resource, context),
+            FilterExpr::Filter(filter) => filter.evaluate(resource, context),
+        }
+    }
+
+    /// All filters at the leaves of the expression, left to right
+    pub fn leaves(&self) -> Vec<&Filter> {
+        let mut leaves = Vec::new();
+        self.collect_leaves(&mut leaves);
+        leaves
+    }
+
+    fn collect_leaves<'a>(&'a self, leaves: &mut Vec<&'a Filter>) {
+        match self {
+            FilterExpr::And(exprs) | FilterExpr::Or(exprs) => {
+                for expr in exprs {
+                    expr.collect_leaves(leaves);
+                }
+            }
+            FilterExpr::Not(expr) => expr.collect_leaves(leaves),
+            FilterExpr::Filter(filter) => leaves.push(filter),
+        }
+    }
+}
+
+/// A checked filter expression ready for evaluation
+pub struct CompiledFilter<'a> {
+    expr: &'a FilterExpr,
+    regexes: Cow<'a, HashMap<String, Regex>>,
+    comparators: Option<&'a HashMap<String, Comparator>>,
+}
+
+impl CompiledFilter<'_> {
+    /// Check whether a resource matches the expression
+    pub fn matches(&self, resource: &Resource) -> bool {
+        self.expr.evaluate(resource, self)
+    }
+
+    fn comparator(&self, path: &str) -> Option<&Comparator> {
+        self.comparators
+            .and_then(|comparators| comparators.get(path))
+    }
+}
+
+impl fmt::Debug for CompiledFilter<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("CompiledFilter")
+            .field("expr", self.expr)
+            .field("regexes", &self.regexes)
+            .finish_non_exhaustive()
+    }
+}
+
+impl From<Filter> for FilterExpr {
+    fn from(filter: Filter) -> Self {
+        FilterExpr::Filter(filter)
+    }
+}
+
+impl From<&[Filter]> for FilterExpr {
+    /// A flat filter list, all of which must match
+    fn from(filters: &[Filter]) -> Self {
+        FilterExpr::And(filters.iter().cloned().map(FilterExpr::Filter).collect())
+    }
+}
+
+impl Default for FilterExpr {
+    fn default() -> Self {
+        Self::all()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::collections::HashMap;
+
+    fn resource(data: serde_json::Value) -> Resource {
+        let data: HashMap<String, serde_json::Value> = serde_json::from_value(data).unwrap();
+        Resource {
+            id: "1".to_string(),
+            data,
+        }
+    }
+
+    fn filter(field: &str, operator: &str, value: serde_json::Value) -> Filter {
+        Filter::new(field, operator.parse().unwrap(), value)
+    }
+
+    fn matches(filter: &Filter, data: serde_json::Value) -> bool {
+        filter.matches(&resource(data)).unwrap()
+    }
+
+    /// age > 60 OR (team == "ops" AND NOT name contains "bot")
+    fn sample_expr() -> FilterExpr {
+        let ops = FilterExpr::from(filter("team", "eq", json!("ops")))
+            .and(FilterExpr::not(filter("name", "contains", json!("bot"))));
+        FilterExpr::from(filter("age", "gt", json!(60))).or(ops)
+    }
+
+    #[test]
+    fn test_expression_deserializes_from_json() {
+        let expr: FilterExpr = serde_json::from_value(json!({
+            "or": [
+                { "field": "age", "operator": "gt", "value": 60 },
+                { "and": [
+                    { "field": "team", "operator": "eq", "value": "ops" },
+                    { "not": { "field": "name", "operator": "contains", "value": "bot" } }
+                ] }
+            ]
+        }))
+        .unwrap();
+        assert_eq!(expr, sample_expr());
+
+        let round_trip: FilterExpr =
+            serde_json::from_value(serde_json::to_value(&expr).unwrap()).unwrap();
+        assert_eq!(round_trip, expr);
+        assert_eq!(expr.leaves().len(), 3);
+    }
+
+    #[test]
+    fn test_expression_evaluation() {
+        let expr = sample_expr();
+        let compiled = expr.compile().unwrap();
+        let matches = |data| compiled.matches(&resource(data));
+
+        assert!(matches(json!({"age": 70, "team": "dev", "name": "a"})));
+        assert!(matches(json!({"age": 20, "team": "ops", "name": "alice"})));
+        assert!(!matches(
+            json!({"age": 20, "team": "ops", "name": "opsbot"})
+        ));
+        assert!(!matches(json!({"age": 20, "team": "dev", "name": "bob"})));
+    }
+
+    #[test]
+    fn test_empty_groups() {
+        let any = resource(json!({}));
+        assert!(FilterExpr::all().matches(&any).unwrap());
+        assert!(!FilterExpr::Or(Vec::new()).matches(&any).unwrap());
+    }
+
+    #[test]
+    fn test_comparison_operators() {
+        let data = json!({"age": 30, "name": "Alice"});
+        assert!(matches(&filter("age", "gte", json!(30)), data.clone()));
+        assert!(!matches(&filter("age", "gt", json!(30)), data.clone()));
+        assert!(matches(&filter("age", "lte", json!(30.0)), data.clone()));
+        assert!(matches(&filter("age", "eq", json!(30.0)), data.clone()));
+        assert!(matches(&filter("name", "lt", json!("Bob")), data.clone()));
+        assert!(!matches(&filter("name", "gt", json!(5)), data.clone()));
+        assert!(matches(
+            &filter("age", "between", json!([18, 30])),
+            data.clone()
+        ));
+        assert!(!matches(&filter("age", "between", json!([31, 40])), data));
+    }
+
+    #[test]
+    fn test_membership_and_string_operators() {
+        let data = json!({"status": "active", "name": "Alice Smith", "tags": ["a", "b"]});
+        assert!(matches(
+            &filter("status", "in", json!(["active", "new"])),
+            data.clone()
+        ));
+        assert!(!matches(
+            &filter("status", "nin", json!(["active"])),
+            data.clone()
+        ));
+        assert!(matches(
+            &filter("name", "startsWith", json!("Ali")),
+            data.clone()
+        ));
+        assert!(matches(
+            &filter("name", "endsWith", json!("Smith")),
+            data.clone()
+        ));
+        assert!(matches(
+            &filter("name", "icontains", json!("alice s")),
+            data.clone()
+        ));
+        assert!(!matches(
+            &filter("name", "contains", json!("alice")),
+            data.clone()
+        ));
+        assert!(matches(
+            &filter("tags", "contains", json!("b")),
+            data.clone()
+        ));
+        assert!(matches(
+            &filter("name", "regex", json!("^A.*h$")),
+            data.clone()
+        ));
+        assert!(matches(
+            &filter("name", "exists", json!(true)),
+            data.clone()
+        ));
+        assert!(matches(
+            &filter("email", "exists", json!(false)),
+            data.clone()
+        ));
+        assert!(!matches(&filter("email", "ne", json!("x")), data));
+    }
+
+    #[test]
+    fn test_null_operators() {
+        let null = json!({"deleted_at": null});
+        let set = json!({"deleted_at": "2024-01-01"});
+        let missing = json!({});
+
+        let is_null = filter("deleted_at", "isNull", json!(null));
+        assert!(matches(&is_null, null.clone()));
+        assert!(matches(&is_null, missing.clone()));
+        assert!(!matches(&is_null, set.clone()));
+
+        let is_not_null = filter("deleted_at", "isNotNull", json!(null));
+        assert!(!matches(&is_not_null, null.clone()));
+        assert!(!matches(&is_not_null, missing.clone()));
+        assert!(matches(&is_not_null, set));
+
+        // Presence and equality to null still tell an explicit null from a missing field
+        assert!(matches(
+            &filter("deleted_at", "exists", json!(true)),
+            null.clone()
+        ));
+        assert!(matches(&filter("deleted_at", "eq", json!(null)), null));
+        assert!(!matches(&filter("deleted_at", "eq", json!(null)), missing));
+    }
+
+    #[test]
+    fn test_invalid_filters_rejected() {
+        let unknown: Result<Filter, _> = serde_json::from_value(json!({
+            "field": "age", "operator": "like", "value": 1
+        }));
+        assert!(unknown.unwrap_err().to_string().contains("like"));
+        assert!("like".parse::<FilterOperator>().is_err());
+
+        let any = resource(json!({"age": 1}));
+        for bad in [
+            filter("age", "in", json!(1)),
+            filter("age", "between", json!([1])),
+            filter("age", "regex", json!("[a-")),
+            filter("age", "exists", json!("yes")),
+        ] {
+            match bad.matches(&any) {
+                Err(MetaRestError::ValidationError(errors)) => {
+                    assert_eq!(errors.violations[0].field, "age")
+                }
+                _ => panic!("Expected ValidationError for {:?}", bad),
+            }
+        }
+    }
+}
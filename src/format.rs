@@ -0,0 +1,379 @@
+//! Semantic string formats
+//!
+//! A [`Format`] narrows a `string` field to a well-known syntax. Fields with the `date` or
+//! `date-time` format are also given a chronological order, so that filters and sorting
+//! compare them as points in time rather than as text.
+
+use crate::query::compare_values;
+use crate::types::Comparator;
+use crate::Field;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fmt;
+use std::net::{Ipv4Addr, Ipv6Addr};
+use std::sync::Arc;
+
+/// Well-known string format
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Format {
+    /// E-mail address such as `alice@example.com`
+    Email,
+    /// Absolute URI with a scheme, such as `https://example.com/a`
+    Uri,
+    /// UUID in its hyphenated form
+    Uuid,
+    /// Calendar date (`2024-02-29`)
+    Date,
+    /// RFC 3339 date-time with an offset (`2024-02-29T12:30:00Z`)
+    DateTime,
+    /// IPv4 address in dotted-decimal form
+    Ipv4,
+    /// IPv6 address
+    Ipv6,
+}
+
+impl Format {
+    /// Name of the format as used in definitions
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Format::Email => "email",
+            Format::Uri => "uri",
+            Format::Uuid => "uuid",
+            Format::Date => "date",
+            Format::DateTime => "date-time",
+            Format::Ipv4 => "ipv4",
+            Format::Ipv6 => "ipv6",
+        }
+    }
+
+    /// Whether a string is valid in this format
+    pub fn is_valid(&self, value: &str) -> bool {
+        match self {
+            Format::Email => is_email(value),
+            Format::Uri => is_uri(value),
+            Format::Uuid => is_uuid(value),
+            Format::Date => parse_date(value).is_some(),
+            Format::DateTime => parse_date_time(value).is_some(),
+            Format::Ipv4 => value.parse::<Ipv4Addr>().is_ok(),
+            Format::Ipv6 => value.parse::<Ipv6Addr>().is_ok(),
+        }
+    }
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+fn is_email(value: &str) -> bool {
+    let (local, domain) = match value.rsplit_once('@') {
+        Some(parts) => parts,
+        None => return false,
+    };
+    let labels: Vec<&str> = domain.split('.').collect();
+    !local.is_empty()
+        && !local.contains(|c: char| c == '@' || c.is_whitespace() || c.is_control())
+        && labels.len() > 1
+        && labels.iter().all(|label| {
+            !label.is_empty()
+                && !label.starts_with('-')
+                && !label.ends_with('-')
+                && label.chars().all(|c| c.is_alphanumeric() || c == '-')
+        })
+}
+
+fn is_uri(value: &str) -> bool {
+    let (scheme, rest) = match value.split_once(':') {
+        Some(parts) => parts,
+        None => return false,
+    };
+    scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+        && scheme
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+        && !rest.is_empty()
+        && !rest.contains(|c: char| c.is_whitespace() || c.is_control())
+}
+
+fn is_uuid(value: &str) -> bool {
+    let groups: Vec<&str> = value.split('-').collect();
+    groups.len() == 5
+        && groups
+            .iter()
+            .zip([8, 4, 4, 4, 12])
+            .all(|(group, len)| group.len() == len && group.chars().all(|c| c.is_ascii_hexdigit()))
+}
+
+/// Parse a fixed-width decimal number
+fn digits(value: &str) -> Option<u32> {
+    if value.is_empty() || !value.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    value.parse().ok()
+}
+
+/// Parse a `YYYY-MM-DD` date into days since 1970-01-01
+fn parse_date(value: &str) -> Option<i64> {
+    if !value.is_ascii() || value.len() != 10 || &value[4..5] != "-" || &value[7..8] != "-" {
+        return None;
+    }
+    let year = digits(value.get(0..4)?)? as i64;
+    let month = digits(value.get(5..7)?)?;
+    let day = digits(value.get(8..10)?)?;
+
+    let leap = year % 4 == 0 && (year % 100 != 0 || year % 400 == 0);
+    let days_in_month = match month {
+        1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+        4 | 6 | 9 | 11 => 30,
+        2 if leap => 29,
+        2 => 28,
+        _ => return None,
+    };
+    if day == 0 || day > days_in_month {
+        return None;
+    }
+
+    // Days from civil, shifting the year to start in March
+    let (year, month) = if month <= 2 {
+        (year - 1, month + 9)
+    } else {
+        (year, month - 3)
+    };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * month as i64 + 2) / 5 + day as i64 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    Some(era * 146_097 + day_of_era - 719_468)
+}
+
+/// Parse an RFC 3339 date-time into seconds and nanoseconds since the Unix epoch
+fn parse_date_time(value: &str) -> Option<(i64, u32)> {
+    if !value.is_ascii() {
+        return None;
+    }
+    let date = parse_date(value.get(0..10)?)?;
+    if !matches!(value.get(10..11)?, "T" | "t" | " ") {
+        return None;
+    }
+    let time = value.get(11..)?;
+    if time.len() < 9 || &time[2..3] != ":" || &time[5..6] != ":" {
+        return None;
+    }
+    let hour = digits(time.get(0..2)?)?;
+    let minute = digits(time.get(3..5)?)?;
+    // 60 allows for leap seconds
+    let second = digits(time.get(6..8)?)?;
+    if hour > 23 || minute > 59 || second > 60 {
+        return None;
+    }
+
+    let mut rest = &time[8..];
+    let mut nanos = 0;
+    if let Some(fraction) = rest.strip_prefix('.') {
+        let len = fraction
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(fraction.len());
+        if len == 0 {
+            return None;
+        }
+        let significant = &fraction[..len.min(9)];
+        nanos = digits(significant)? * 10u32.pow(9 - significant.len() as u32);
+        rest = &fraction[len..];
+    }
+
+    let offset = match rest {
+        "Z" | "z" => 0,
+        _ => {
+            let sign = match rest.get(0..1)? {
+                "+" => 1,
+                "-" => -1,
+                _ => return None,
+            };
+            if rest.len() != 6 || &rest[3..4] != ":" {
+                return None;
+            }
+            let hours = digits(&rest[1..3])?;
+            let minutes = digits(&rest[4..6])?;
+            if hours > 23 || minutes > 59 {
+                return None;
+            }
+            sign * (hours * 3600 + minutes * 60) as i64
+        }
+    };
+
+    let seconds = date * 86_400 + (hour * 3600 + minute * 60 + second) as i64 - offset;
+    Some((seconds, nanos))
+}
+
+/// Calendar date of a day count since 1970-01-01
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    // Months are counted from March, as in `parse_date`
+    let month = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * month + 2) / 5 + 1) as u32;
+    let month = if month < 10 { month + 3 } else { month - 9 } as u32;
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+/// Format a day count since 1970-01-01 as a `date`
+pub(crate) fn format_date(days: i64) -> String {
+    let (year, month, day) = civil_from_days(days);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+/// Format a point in time as a UTC `date-time` with millisecond precision
+pub(crate) fn format_date_time(seconds: i64, nanos: u32) -> String {
+    let (days, time) = (seconds.div_euclid(86_400), seconds.rem_euclid(86_400));
+    format!(
+        "{}T{:02}:{:02}:{:02}.{:03}Z",
+        format_date(days),
+        time / 3600,
+        time % 3600 / 60,
+        time % 60,
+        nanos / 1_000_000
+    )
+}
+
+/// Point in time of a date or date-time string; dates are taken as midnight UTC
+pub(crate) fn parse_instant(value: &str) -> Option<(i64, u32)> {
+    if value.len() < 10 || !value.starts_with(|c: char| c.is_ascii_digit()) {
+        return None;
+    }
+    match value.len() {
+        10 => parse_date(value).map(|days| (days * 86_400, 0)),
+        _ => parse_date_time(value),
+    }
+}
+
+/// Chronological order of date and date-time strings
+///
+/// Equal points in time written differently are ordered by their text, so that only
+/// identical values are equal. Other values come after points in time, in their JSON order.
+fn compare_instants(a: &serde_json::Value, b: &serde_json::Value) -> Ordering {
+    let instant = |value: &serde_json::Value| value.as_str().and_then(parse_instant);
+    match (instant(a), instant(b)) {
+        (Some(x), Some(y)) => x.cmp(&y).then_with(|| compare_values(a, b)),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => compare_values(a, b),
+    }
+}
+
+/// Chronological comparators of fields with the `date` or `date-time` format, by path
+pub(crate) fn comparators(fields: &[Field], prefix: &str) -> HashMap<String, Comparator> {
+    let mut comparators: HashMap<String, Comparator> = HashMap::new();
+    for field in fields {
+        let path = match prefix {
+            "" => field.name.clone(),
+            _ => format!("{}.{}", prefix, field.name),
+        };
+        let format = field.validation.as_ref().and_then(|rule| rule.format);
+        if matches!(format, Some(Format::Date | Format::DateTime)) {
+            comparators.insert(path.clone(), Arc::new(compare_instants));
+        }
+        if let Some(nested) = &field.fields {
+            comparators.extend(self::comparators(nested, &path));
+        }
+    }
+    comparators
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_formats() {
+        let cases = [
+            (Format::Email, "alice@example.com", "alice@example"),
+            (
+                Format::Email,
+                "a.b+c@mail.example.org",
+                "alice example@x.com",
+            ),
+            (Format::Uri, "https://example.com/a?b=c", "example.com"),
+            (Format::Uri, "urn:isbn:0451450523", "1http://x"),
+            (
+                Format::Uuid,
+                "123e4567-e89b-12d3-a456-426614174000",
+                "123e4567e89b12d3a456426614174000",
+            ),
+            (Format::Date, "2024-02-29", "2023-02-29"),
+            (Format::Date, "1999-12-31", "1999-12-31T00:00:00Z"),
+            (
+                Format::DateTime,
+                "2024-02-29T12:30:00Z",
+                "2024-02-29T24:00:00Z",
+            ),
+            (
+                Format::DateTime,
+                "2024-02-29T12:30:00.123+02:00",
+                "2024-02-29T12:30:00",
+            ),
+            (Format::Ipv4, "192.168.0.1", "192.168.0.256"),
+            (Format::Ipv6, "2001:db8::1", "2001:db8::g"),
+        ];
+        for (format, valid, invalid) in cases {
+            assert!(
+                format.is_valid(valid),
+                "{} should be a valid {}",
+                valid,
+                format
+            );
+            assert!(
+                !format.is_valid(invalid),
+                "{} should not be a valid {}",
+                invalid,
+                format
+            );
+        }
+    }
+
+    #[test]
+    fn test_instants() {
+        assert_eq!(parse_instant("1970-01-01"), Some((0, 0)));
+        assert_eq!(parse_instant("1970-01-02T00:00:00Z"), Some((86_400, 0)));
+        assert_eq!(
+            parse_instant("2024-01-01T12:00:00+02:00"),
+            parse_instant("2024-01-01T10:00:00Z")
+        );
+        assert_eq!(
+            parse_instant("2000-03-01T00:00:00.5Z"),
+            Some((951_868_800, 500_000_000))
+        );
+        assert_eq!(parse_instant("1969-12-31T23:59:59Z"), Some((-1, 0)));
+        assert_eq!(parse_instant("Alice"), None);
+    }
+
+    #[test]
+    fn test_formatting_round_trips() {
+        for (seconds, nanos) in [
+            (0, 0),
+            (951_782_400, 0),
+            (1_709_209_800, 123_000_000),
+            (-1, 0),
+        ] {
+            let text = format_date_time(seconds, nanos);
+            assert!(Format::DateTime.is_valid(&text), "{}", text);
+            assert_eq!(parse_instant(&text), Some((seconds, nanos)));
+        }
+        assert_eq!(format_date(19_782), "2024-02-29");
+        assert_eq!(format_date_time(-1, 0), "1969-12-31T23:59:59.000Z");
+    }
+
+    #[test]
+    fn test_format_names() {
+        let format: Format = serde_json::from_str("\"date-time\"").unwrap();
+        assert_eq!(format, Format::DateTime);
+        assert_eq!(serde_json::to_string(&Format::Ipv6).unwrap(), "\"ipv6\"");
+    }
+}
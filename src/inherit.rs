@@ -0,0 +1,295 @@
+//! Inheritance between resource definitions
+//!
+//! A definition can `extend` one base definition and include any number of `mixins`, which
+//! are definitions too:
+//!
+//! ```json
+//! {
+//!   "name": "invoices",
+//!   "extends": "owned",
+//!   "mixins": ["timestamps"],
+//!   "fields": [{ "name": "total", "field_type": "decimal", "required": true }]
+//! }
+//! ```
+//!
+//! The definition inherits the fields, security policy, rules and shared types of its base and
+//! mixins. Its own fields and shared types replace inherited ones of the same name, and its own
+//! security policy replaces the inherited one. Inherited fields, policies and shared types that
+//! come from several sources must be identical; otherwise the definition is rejected.
+
+use crate::{Field, MetaRestError, ResourceDefinition, ResourceRule, SecurityPolicy};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+
+/// Whether two parts of definitions are the same
+fn same<T: Serialize>(a: &T, b: &T) -> bool {
+    serde_json::to_value(a).ok() == serde_json::to_value(b).ok()
+}
+
+/// Parts of a definition inherited from its base and mixins, with the name of their source
+struct Inherited<'a> {
+    own: &'a ResourceDefinition,
+    fields: Vec<(String, Field)>,
+    security: Option<(String, SecurityPolicy)>,
+    shared_types: BTreeMap<String, (String, Field)>,
+    rules: Vec<ResourceRule>,
+}
+
+impl<'a> Inherited<'a> {
+    fn new(own: &'a ResourceDefinition) -> Self {
+        Self {
+            own,
+            fields: Vec::new(),
+            security: None,
+            shared_types: BTreeMap::new(),
+            rules: Vec::new(),
+        }
+    }
+
+    /// Add the parts of a resolved base or mixin; conflicts only matter for parts the
+    /// definition does not replace
+    fn add(&mut self, source: &str, base: ResourceDefinition) -> Result<(), MetaRestError> {
+        let own = self.own;
+        let conflict = |what: String, other: &str| {
+            Err(MetaRestError::InvalidDefinition(format!(
+                "Resource '{}' inherits conflicting {} from '{}' and '{}'",
+                own.name, what, other, source
+            )))
+        };
+
+        for field in base.fields {
+            let replaced = own.fields.iter().any(|f| f.name == field.name);
+            match self.fields.iter().find(|(_, f)| f.name == field.name) {
+                Some((other, f)) if !replaced && !same(f, &field) => {
+                    return conflict(format!("definitions of field '{}'", field.name), other);
+                }
+                Some(_) => {}
+                None => self.fields.push((source.to_string(), field)),
+            }
+        }
+        match (&self.security, base.security) {
+            _ if own.security.is_some() => {}
+            (Some((other, policy)), Some(security)) if !same(policy, &security) => {
+                return conflict("security policies".to_string(), other);
+            }
+            (None, Some(security)) => self.security = Some((source.to_string(), security)),
+            _ => {}
+        }
+        for (name, field) in base.shared_types {
+            if own.shared_types.contains_key(&name) {
+                continue;
+            }
+            match self.shared_types.get(&name) {
+                Some((other, f)) if !same(f, &field) => {
+                    return conflict(format!("shared types '{}'", name), other);
+                }
+                Some(_) => {}
+                None => {
+                    self.shared_types.insert(name, (source.to_string(), field));
+                }
+            }
+        }
+        for rule in base.rules {
+            if !self.rules.contains(&rule) {
+                self.rules.push(rule);
+            }
+        }
+        Ok(())
+    }
+
+    /// The definition with its own parts applied on top of the inherited ones
+    ///
+    /// Fields that replace inherited ones keep their inherited position.
+    fn apply(self) -> ResourceDefinition {
+        let own = self.own;
+        let mut fields: Vec<Field> = self.fields.into_iter().map(|(_, field)| field).collect();
+        for field in &own.fields {
+            match fields.iter_mut().find(|f| f.name == field.name) {
+                Some(inherited) => *inherited = field.clone(),
+                None => fields.push(field.clone()),
+            }
+        }
+        let mut shared_types: BTreeMap<String, Field> = self
+            .shared_types
+            .into_iter()
+            .map(|(name, (_, field))| (name, field))
+            .collect();
+        shared_types.extend(own.shared_types.clone());
+        let mut rules = self.rules;
+        for rule in &own.rules {
+            if !rules.contains(rule) {
+                rules.push(rule.clone());
+            }
+        }
+
+        ResourceDefinition {
+            fields,
+            security: own
+                .security
+                .clone()
+                .or(self.security.map(|(_, security)| security)),
+            rules,
+            shared_types,
+            extends: None,
+            mixins: Vec::new(),
+            ..own.clone()
+        }
+    }
+}
+
+/// Resolver of the bases and mixins of definitions
+struct Resolver<'a> {
+    bases: &'a [ResourceDefinition],
+    resolved: HashMap<String, ResourceDefinition>,
+    /// Base definitions being resolved, to detect cycles
+    stack: Vec<String>,
+}
+
+impl Resolver<'_> {
+    fn resolve(
+        &mut self,
+        definition: &ResourceDefinition,
+    ) -> Result<ResourceDefinition, MetaRestError> {
+        if definition.extends.is_none() && definition.mixins.is_empty() {
+            return Ok(definition.clone());
+        }
+        let mut inherited = Inherited::new(definition);
+        for source in definition.extends.iter().chain(&definition.mixins) {
+            let base = self.resolve_base(&definition.name, source)?;
+            inherited.add(source, base)?;
+        }
+        Ok(inherited.apply())
+    }
+
+    fn resolve_base(
+        &mut self,
+        name: &str,
+        source: &str,
+    ) -> Result<ResourceDefinition, MetaRestError> {
+        if let Some(base) = self.resolved.get(source) {
+            return Ok(base.clone());
+        }
+        if let Some(start) = self.stack.iter().position(|n| n == source) {
+            let mut cycle = self.stack[start..].to_vec();
+            cycle.push(source.to_string());
+            return Err(MetaRestError::InvalidDefinition(format!(
+                "Definition '{}' inherits from itself: {}",
+                source,
+                cycle.join(" -> ")
+            )));
+        }
+        let base = self
+            .bases
+            .iter()
+            .find(|base| base.name == source)
+            .ok_or_else(|| {
+                MetaRestError::InvalidDefinition(format!(
+                    "Resource '{}' inherits from unknown definition '{}'",
+                    name, source
+                ))
+            })?;
+
+        self.stack.push(source.to_string());
+        let resolved = self.resolve(base)?;
+        self.stack.pop();
+        self.resolved.insert(source.to_string(), resolved.clone());
+        Ok(resolved)
+    }
+}
+
+/// The definition with the parts of its base and mixins merged in
+pub(crate) fn resolve_inheritance(
+    definition: &ResourceDefinition,
+    bases: &[ResourceDefinition],
+) -> Result<ResourceDefinition, MetaRestError> {
+    let mut resolver = Resolver {
+        bases,
+        resolved: HashMap::new(),
+        stack: Vec::new(),
+    };
+    resolver.resolve(definition)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn definition(definition: serde_json::Value) -> ResourceDefinition {
+        serde_json::from_value(definition).unwrap()
+    }
+
+    fn bases() -> Vec<ResourceDefinition> {
+        vec![
+            definition(json!({
+                "name": "timestamps",
+                "fields": [
+                    { "name": "created_at", "field_type": "string", "read_only": true },
+                    { "name": "updated_at", "field_type": "string", "read_only": true }
+                ]
+            })),
+            definition(json!({
+                "name": "owned",
+                "mixins": ["timestamps"],
+                "fields": [{ "name": "owner", "field_type": "string", "required": true }],
+                "security": { "require_auth": true }
+            })),
+        ]
+    }
+
+    #[test]
+    fn test_fields_and_security_inherited() {
+        let invoices = definition(json!({
+            "name": "invoices",
+            "extends": "owned",
+            "mixins": ["timestamps"],
+            "fields": [
+                { "name": "total", "field_type": "number" },
+                { "name": "owner", "field_type": "string" }
+            ]
+        }));
+
+        let resolved = resolve_inheritance(&invoices, &bases()).unwrap();
+        let names: Vec<&str> = resolved.fields.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(names, vec!["created_at", "updated_at", "owner", "total"]);
+        assert_eq!(resolved.field("owner").unwrap().required, None);
+        assert!(resolved.security.unwrap().require_auth);
+        assert!(resolved.extends.is_none() && resolved.mixins.is_empty());
+    }
+
+    #[test]
+    fn test_conflicts_and_cycles_rejected() {
+        let mut bases = bases();
+        bases.push(definition(json!({
+            "name": "audited",
+            "fields": [{ "name": "updated_at", "field_type": "integer" }],
+            "security": { "require_auth": false }
+        })));
+        let error = |definition, bases: &[ResourceDefinition]| match resolve_inheritance(
+            &definition,
+            bases,
+        ) {
+            Err(MetaRestError::InvalidDefinition(msg)) => msg,
+            _ => panic!("Expected InvalidDefinition error"),
+        };
+
+        let conflicting = definition(json!({
+            "name": "notes", "extends": "owned", "mixins": ["audited"], "fields": []
+        }));
+        assert!(error(conflicting, &bases)
+            .contains("conflicting definitions of field 'updated_at' from 'owned' and 'audited'"));
+
+        let overridden = definition(json!({
+            "name": "notes", "extends": "owned", "mixins": ["audited"],
+            "fields": [{ "name": "updated_at", "field_type": "string" }]
+        }));
+        assert!(error(overridden, &bases).contains("conflicting security policies"));
+
+        let unknown = definition(json!({ "name": "notes", "extends": "ownd", "fields": [] }));
+        assert!(error(unknown, &bases).contains("unknown definition 'ownd'"));
+
+        bases[0].mixins.push("owned".to_string());
+        let cyclic = definition(json!({ "name": "notes", "mixins": ["owned"], "fields": [] }));
+        assert!(error(cyclic, &bases).contains("owned -> timestamps -> owned"));
+    }
+}
@@ -4,27 +4,118 @@
 //! instead of implementing each resource manually. It provides automatic CRUD operations,
 //! validation, filtering, and storage management.
 
+use regex::Regex;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::error::Error;
 use std::fmt;
 
+mod decimal;
+mod defaults;
+mod filter;
+mod format;
+mod inherit;
+mod modifiers;
+mod normalize;
+mod query;
+mod query_string;
+mod refs;
+mod rules;
+mod security;
+#[cfg(feature = "server")]
+pub mod server;
+mod service;
+mod types;
+mod unknown;
+mod validation;
+mod validators;
+
+pub use defaults::Generator;
+pub use filter::{CompiledFilter, Filter, FilterExpr, FilterOperator};
+pub use format::Format;
+pub use normalize::Transform;
+pub use query::{CursorPage, CursorQuery, ListQuery, NullsOrder, Page, SortDirection, SortKey};
+pub use query_string::QueryParams;
+pub use rules::ResourceRule;
+pub use security::{Access, AccessRule, CallerContext, SecurityPolicy, ANY_ROLE};
+pub use service::{ServiceDefinition, ServiceRegistry, ServiceSettings};
+pub use types::{Comparator, FieldType, Normalizer, TypeCheck, TypeRegistry, BUILT_IN_TYPES};
+pub use unknown::UnknownFields;
+pub use validation::{RuleKind, ValidationErrors, Violation};
+pub use validators::{Validator, ValidatorRegistry};
+
+use defaults::{apply_defaults, check_defaults};
+use filter::check_filters;
+use modifiers::{apply_modifiers, check_modifiers, check_readable, redact};
+use normalize::{check_normalization, normalize_fields};
+use rules::{check_rules, evaluate_rules, CompiledRule};
+use unknown::{check_unknown_fields, strip_unknown_fields};
+use validation::{compile_patterns, validate_fields};
+
 /// Represents a field in a resource definition
-#[derive(Debug, Clone, Serialize, Deserialize)]
+///
+/// Flags left unset are off, except on a field that refers to a shared type, where they keep
+/// the value of the shared type.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Field {
-    /// Name of the field
+    /// Name of the field (empty for array item schemas)
+    #[serde(default)]
     pub name: String,
-    /// Type of the field (e.g., "string", "number", "boolean")
+    /// Type of the field: a built-in type ("string", "number", "integer", "decimal",
+    /// "boolean", "array", "object") or one registered in a [`TypeRegistry`]; may be left
+    /// out when the field refers to a shared type
+    #[serde(default, skip_serializing_if = "String::is_empty")]
     pub field_type: String,
+    /// Name of a shared type of the definition that the field is based on; properties set
+    /// on the field replace those of the shared type
+    #[serde(rename = "$ref", default, skip_serializing_if = "Option::is_none")]
+    pub reference: Option<String>,
     /// Whether the field is required
-    pub required: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub required: Option<bool>,
+    /// Whether the field may be `null`; `required` only asks for the field to be present
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nullable: Option<bool>,
     /// Optional validation rules
     #[serde(skip_serializing_if = "Option::is_none")]
     pub validation: Option<ValidationRule>,
+    /// Sub-schema of an `object` field
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fields: Option<Vec<Field>>,
+    /// Schema of the elements of an `array` field
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub items: Option<Box<Field>>,
+    /// Values the field is restricted to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub allowed_values: Option<Vec<serde_json::Value>>,
+    /// Value filled in when the field is missing on creation
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default: Option<serde_json::Value>,
+    /// Generator of the value when the field is missing on creation
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub generate: Option<Generator>,
+    /// Whether clients are prevented from setting the field
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub read_only: Option<bool>,
+    /// Whether the field is stored but never returned to clients
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub write_only: Option<bool>,
+    /// Whether the field cannot change after creation
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub immutable: Option<bool>,
+    /// Names of custom validators run on the value, registered in a [`ValidatorRegistry`]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub validators: Option<Vec<String>>,
+    /// Transformations applied to string input, in order, before validation
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub transforms: Option<Vec<Transform>>,
+    /// Whether string input is converted to the field's number or boolean type
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub coerce: Option<bool>,
 }
 
 /// Validation rules for fields
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct ValidationRule {
     /// Minimum value/length
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -32,31 +123,96 @@ pub struct ValidationRule {
     /// Maximum value/length
     #[serde(skip_serializing_if = "Option::is_none")]
     pub max: Option<f64>,
-    /// Regex pattern for string validation
+    /// Regex pattern for string validation (not implicitly anchored)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub pattern: Option<String>,
-}
-
-/// Security policy for a resource
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct SecurityPolicy {
-    /// Whether authentication is required
-    pub require_auth: bool,
-    /// Allowed roles for access
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub allowed_roles: Option<Vec<String>>,
+    /// Well-known format of a string value
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub format: Option<Format>,
+    /// Maximum number of fractional digits of a `decimal`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scale: Option<u32>,
+    /// Maximum number of digits of a `decimal`, including `scale` fractional digits
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub precision: Option<u32>,
+    /// Step that numeric values must be a multiple of
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub multiple_of: Option<f64>,
+    /// Minimum number of array items
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_items: Option<usize>,
+    /// Maximum number of array items
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_items: Option<usize>,
+    /// Whether array items must be distinct
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub unique_items: Option<bool>,
 }
 
 /// Resource meta-description defining the structure and behavior
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct ResourceDefinition {
     /// Name of the resource
     pub name: String,
-    /// Fields that make up the resource
+    /// Name of a base definition whose fields, security policy, rules and shared types the
+    /// resource inherits
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub extends: Option<String>,
+    /// Names of definitions whose parts the resource includes, like those of its base
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub mixins: Vec<String>,
+    /// Fields that make up the resource, replacing inherited fields of the same name
+    #[serde(default)]
     pub fields: Vec<Field>,
     /// Security policy for the resource
     #[serde(skip_serializing_if = "Option::is_none")]
     pub security: Option<SecurityPolicy>,
+    /// Handling of fields that are not declared in `fields`
+    #[serde(default, skip_serializing_if = "UnknownFields::is_allow")]
+    pub unknown_fields: UnknownFields,
+    /// Rules relating several fields
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub rules: Vec<ResourceRule>,
+    /// Field shapes that fields refer to with `$ref`, by name
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub shared_types: BTreeMap<String, Field>,
+}
+
+impl ResourceDefinition {
+    /// Look up a field by name, or a nested field by dotted path such as `address.city`
+    pub fn field(&self, path: &str) -> Option<&Field> {
+        let mut fields = self.fields.as_slice();
+        let mut found = None;
+        for name in path.split('.') {
+            let field = fields.iter().find(|field| field.name == name)?;
+            fields = field.fields.as_deref().unwrap_or_default();
+            found = Some(field);
+        }
+        found
+    }
+
+    /// Merge the parts of the base definition and mixins, looked up by name in `bases`
+    ///
+    /// Fails with [`MetaRestError::InvalidDefinition`] if a base or mixin is not in `bases`,
+    /// if definitions inherit from each other in a cycle, or if two of them contribute
+    /// different fields, security policies or shared types of the same name that the
+    /// definition does not replace.
+    pub fn resolve_inheritance(
+        &mut self,
+        bases: &[ResourceDefinition],
+    ) -> Result<(), MetaRestError> {
+        *self = inherit::resolve_inheritance(self, bases)?;
+        Ok(())
+    }
+
+    /// Replace the references of fields to shared types with the shapes they refer to
+    ///
+    /// Fails with [`MetaRestError::InvalidDefinition`] if a field refers to an unknown
+    /// shared type, or if shared types refer to each other in a cycle.
+    pub fn resolve_refs(&mut self) -> Result<(), MetaRestError> {
+        self.fields = refs::resolve_refs(&self.fields, &self.shared_types)?;
+        Ok(())
+    }
 }
 
 /// A resource instance with dynamic data
@@ -68,15 +224,13 @@ pub struct Resource {
     pub data: HashMap<String, serde_json::Value>,
 }
 
-/// Filter criteria for querying resources
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct Filter {
-    /// Field name to filter on
-    pub field: String,
-    /// Filter operator (e.g., "eq", "gt", "lt", "contains")
-    pub operator: String,
-    /// Value to compare against
-    pub value: serde_json::Value,
+impl Resource {
+    /// Value of a field, or of a nested field by dotted path such as `address.city`
+    pub fn value(&self, path: &str) -> Option<&serde_json::Value> {
+        let mut segments = path.split('.');
+        let first = self.data.get(segments.next()?)?;
+        segments.try_fold(first, |value, segment| value.get(segment))
+    }
 }
 
 /// Error types for meta-REST operations
@@ -85,11 +239,17 @@ pub enum MetaRestError {
     /// Resource not found
     NotFound(String),
     /// Validation failed
-    ValidationError(String),
+    ValidationError(ValidationErrors),
     /// Storage error
     StorageError(String),
     /// Invalid operation
     InvalidOperation(String),
+    /// Meta-description is malformed or inconsistent
+    InvalidDefinition(String),
+    /// Caller is not authenticated
+    Unauthorized(String),
+    /// Caller is not allowed to perform the operation
+    Forbidden(String),
 }
 
 impl fmt::Display for MetaRestError {
@@ -99,6 +259,9 @@ impl fmt::Display for MetaRestError {
             MetaRestError::ValidationError(msg) => write!(f, "Validation error: {}", msg),
             MetaRestError::StorageError(msg) => write!(f, "Storage error: {}", msg),
             MetaRestError::InvalidOperation(msg) => write!(f, "Invalid operation: {}", msg),
+            MetaRestError::InvalidDefinition(msg) => write!(f, "Invalid definition: {}", msg),
+            MetaRestError::Unauthorized(msg) => write!(f, "Unauthorized: {}", msg),
+            MetaRestError::Forbidden(msg) => write!(f, "Forbidden: {}", msg),
         }
     }
 }
@@ -124,52 +287,53 @@ pub trait Storage: Send + Sync {
 
     /// Filter resources based on criteria
     fn filter(&self, filters: &[Filter]) -> Result<Vec<Resource>, MetaRestError>;
+
+    /// Filter resources with a boolean filter expression
+    ///
+    /// Backends should translate the expression into their native query language. The
+    /// default implementation lists all resources and evaluates the expression in memory.
+    fn filter_expr(&self, expr: &FilterExpr) -> Result<Vec<Resource>, MetaRestError> {
+        let compiled = expr.compile()?;
+        Ok(self
+            .list()?
+            .into_iter()
+            .filter(|resource| compiled.matches(resource))
+            .collect())
+    }
+
+    /// Filter, sort and paginate resources
+    ///
+    /// The default implementation filters with [`Storage::filter_expr`] and sorts and pages
+    /// the results in memory.
+    fn query(&self, query: &ListQuery) -> Result<Page, MetaRestError> {
+        let resources = self.filter_expr(&query.filter)?;
+        Ok(query.paginate(resources))
+    }
+
+    /// Filter and sort resources and return the page following the query's cursor
+    ///
+    /// The returned continuation token encodes the position of the last resource, so
+    /// paging stays consistent while resources are inserted or removed. The default
+    /// implementation filters with [`Storage::filter_expr`] and seeks in memory.
+    fn query_cursor(&self, query: &CursorQuery) -> Result<CursorPage, MetaRestError> {
+        let resources = self.filter_expr(&query.filter)?;
+        query.paginate(resources)
+    }
 }
 
 /// In-memory storage implementation
+///
+/// Resources are kept ordered by id, so listing is deterministic.
 #[derive(Debug, Default)]
 pub struct InMemoryStorage {
-    resources: HashMap<String, Resource>,
+    resources: BTreeMap<String, Resource>,
 }
 
 impl InMemoryStorage {
     /// Create a new in-memory storage
     pub fn new() -> Self {
         Self {
-            resources: HashMap::new(),
-        }
-    }
-
-    fn matches_filter(resource: &Resource, filter: &Filter) -> bool {
-        if let Some(value) = resource.data.get(&filter.field) {
-            match filter.operator.as_str() {
-                "eq" => value == &filter.value,
-                "ne" => value != &filter.value,
-                "gt" => {
-                    if let (Some(v1), Some(v2)) = (value.as_f64(), filter.value.as_f64()) {
-                        v1 > v2
-                    } else {
-                        false
-                    }
-                }
-                "lt" => {
-                    if let (Some(v1), Some(v2)) = (value.as_f64(), filter.value.as_f64()) {
-                        v1 < v2
-                    } else {
-                        false
-                    }
-                }
-                "contains" => {
-                    if let (Some(v1), Some(v2)) = (value.as_str(), filter.value.as_str()) {
-                        v1.contains(v2)
-                    } else {
-                        false
-                    }
-                }
-                _ => false,
-            }
-        } else {
-            false
+            resources: BTreeMap::new(),
         }
     }
 }
@@ -216,146 +380,308 @@ impl Storage for InMemoryStorage {
     }
 
     fn filter(&self, filters: &[Filter]) -> Result<Vec<Resource>, MetaRestError> {
+        self.filter_expr(&FilterExpr::from(filters))
+    }
+
+    fn filter_expr(&self, expr: &FilterExpr) -> Result<Vec<Resource>, MetaRestError> {
+        let compiled = expr.compile()?;
         let results: Vec<Resource> = self
             .resources
             .values()
-            .filter(|resource| {
-                filters
-                    .iter()
-                    .all(|filter| Self::matches_filter(resource, filter))
-            })
+            .filter(|resource| compiled.matches(resource))
             .cloned()
             .collect();
         Ok(results)
     }
 }
 
+/// Extensions available to the fields of a resource definition
+#[derive(Debug, Clone, Default)]
+pub struct ManagerOptions {
+    /// Custom validators that fields refer to by name
+    pub validators: ValidatorRegistry,
+    /// Custom field types, next to the built-in ones
+    pub types: TypeRegistry,
+}
+
 /// Resource manager that handles CRUD operations with validation
 pub struct ResourceManager<S: Storage> {
     definition: ResourceDefinition,
     storage: S,
+    /// Compiled validation patterns by field path
+    patterns: HashMap<String, Regex>,
+    /// Custom validators referenced by the fields
+    validators: ValidatorRegistry,
+    /// Custom field types
+    types: TypeRegistry,
+    /// Comparators of fields with custom types or date formats, by field path
+    comparators: HashMap<String, Comparator>,
+    /// Resource rules of the definition, with their conditions compiled
+    rules: Vec<CompiledRule>,
 }
 
 impl<S: Storage> ResourceManager<S> {
     /// Create a new resource manager with a definition and storage backend
-    pub fn new(definition: ResourceDefinition, storage: S) -> Self {
-        Self {
+    ///
+    /// Fails with [`MetaRestError::InvalidDefinition`] if:
+    ///
+    /// - a validation pattern is not a valid regular expression
+    /// - nested fields, an item schema, a format or a generator is used on a field of the
+    ///   wrong type
+    /// - a field has an unknown type or refers to an unregistered validator
+    /// - a default or generated value does not fit its field
+    /// - field modifiers conflict or are used below the top level
+    /// - transforms or coercion do not fit the field type
+    /// - a resource rule refers to unknown fields or is malformed
+    /// - references to shared types cannot be resolved
+    /// - the definition still has a base or mixins, which
+    ///   [`ResourceDefinition::resolve_inheritance`] merges first
+    pub fn new(definition: ResourceDefinition, storage: S) -> Result<Self, MetaRestError> {
+        Self::with_options(definition, storage, ManagerOptions::default())
+    }
+
+    /// Create a resource manager whose fields can use the custom validators and field types
+    /// of the options
+    ///
+    /// Fails like [`new`](ResourceManager::new).
+    pub fn with_options(
+        mut definition: ResourceDefinition,
+        storage: S,
+        options: ManagerOptions,
+    ) -> Result<Self, MetaRestError> {
+        let ManagerOptions { validators, types } = options;
+        definition.resolve_inheritance(&[])?;
+        definition.resolve_refs()?;
+        types.check_fields(&definition.fields, "")?;
+        let mut patterns = HashMap::new();
+        compile_patterns(&definition.fields, "", &mut patterns)?;
+        validators.check_fields(&definition.fields, "")?;
+        check_defaults(&definition.fields, "", &patterns, &validators, &types)?;
+        check_modifiers(&definition.fields)?;
+        check_normalization(&definition.fields, "")?;
+        let mut comparators = format::comparators(&definition.fields, "");
+        comparators.extend(types.comparators(&definition.fields, ""));
+        let rules = check_rules(&definition, &comparators)?;
+
+        Ok(Self {
             definition,
             storage,
-        }
+            patterns,
+            validators,
+            types,
+            comparators,
+            rules,
+        })
     }
 
     /// Validate a resource against the definition
+    ///
+    /// Every field and resource rule is checked and all failures are reported together.
+    /// Undeclared fields are violations when the definition rejects unknown fields.
     pub fn validate(&self, resource: &Resource) -> Result<(), MetaRestError> {
-        // Check required fields
-        for field in &self.definition.fields {
-            if field.required && !resource.data.contains_key(&field.name) {
-                return Err(MetaRestError::ValidationError(format!(
-                    "Required field '{}' is missing",
-                    field.name
-                )));
-            }
+        let mut errors = ValidationErrors::default();
+        validate_fields(
+            &self.definition.fields,
+            "",
+            |name| resource.data.get(name),
+            &self.patterns,
+            &self.validators,
+            &self.types,
+            &mut errors,
+        );
+        evaluate_rules(&self.rules, resource, &self.comparators, &mut errors);
+        if self.definition.unknown_fields == UnknownFields::Reject {
+            check_unknown_fields(&self.definition.fields, &resource.data, &mut errors);
+        }
 
-            // Validate field type and rules if present
-            if let Some(value) = resource.data.get(&field.name) {
-                // Type checking
-                let valid_type = match field.field_type.as_str() {
-                    "string" => value.is_string(),
-                    "number" => value.is_number(),
-                    "boolean" => value.is_boolean(),
-                    "array" => value.is_array(),
-                    "object" => value.is_object(),
-                    _ => true, // Unknown types are allowed
-                };
-
-                if !valid_type {
-                    return Err(MetaRestError::ValidationError(format!(
-                        "Field '{}' has invalid type, expected '{}'",
-                        field.name, field.field_type
-                    )));
-                }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(MetaRestError::ValidationError(errors))
+        }
+    }
 
-                // Validation rules
-                if let Some(rules) = &field.validation {
-                    if let Some(min) = rules.min {
-                        if field.field_type == "number" {
-                            if let Some(num) = value.as_f64() {
-                                if num < min {
-                                    return Err(MetaRestError::ValidationError(format!(
-                                        "Field '{}' value {} is less than minimum {}",
-                                        field.name, num, min
-                                    )));
-                                }
-                            }
-                        } else if field.field_type == "string" {
-                            if let Some(s) = value.as_str() {
-                                if s.len() < min as usize {
-                                    return Err(MetaRestError::ValidationError(format!(
-                                        "Field '{}' length is less than minimum {}",
-                                        field.name, min
-                                    )));
-                                }
-                            }
-                        }
-                    }
+    /// Check the resource's security policy for a caller
+    fn authorize(&self, access: Access, caller: &CallerContext) -> Result<(), MetaRestError> {
+        match &self.definition.security {
+            Some(policy) => policy.authorize(access, caller),
+            None => Ok(()),
+        }
+    }
 
-                    if let Some(max) = rules.max {
-                        if field.field_type == "number" {
-                            if let Some(num) = value.as_f64() {
-                                if num > max {
-                                    return Err(MetaRestError::ValidationError(format!(
-                                        "Field '{}' value {} is greater than maximum {}",
-                                        field.name, num, max
-                                    )));
-                                }
-                            }
-                        } else if field.field_type == "string" {
-                            if let Some(s) = value.as_str() {
-                                if s.len() > max as usize {
-                                    return Err(MetaRestError::ValidationError(format!(
-                                        "Field '{}' length is greater than maximum {}",
-                                        field.name, max
-                                    )));
-                                }
-                            }
-                        }
-                    }
-                }
-            }
+    /// Check filters and sort keys against the definition
+    fn check_query(&self, filters: &[&Filter], sort: &[SortKey]) -> Result<(), MetaRestError> {
+        let paths = filters
+            .iter()
+            .map(|filter| filter.field.as_str())
+            .chain(sort.iter().map(|key| key.field.as_str()));
+        check_readable(&self.definition.fields, paths)?;
+        check_filters(filters.iter().copied(), &self.definition)
+    }
+
+    /// Whether a query filters or sorts on a field compared by its custom type or date format
+    fn uses_comparators(&self, filters: &[&Filter], sort: &[SortKey]) -> bool {
+        filters
+            .iter()
+            .map(|filter| filter.field.as_str())
+            .chain(sort.iter().map(|key| key.field.as_str()))
+            .any(|path| self.comparators.contains_key(path))
+    }
+
+    /// Filter resources in memory, comparing fields with their comparators
+    ///
+    /// Storage backends do not know about custom types and date formats, so queries on such
+    /// fields list all resources instead of filtering in the backend.
+    fn filter_in_memory(&self, expr: &FilterExpr) -> Result<Vec<Resource>, MetaRestError> {
+        let compiled = expr.compile_with(Some(&self.comparators))?;
+        Ok(self
+            .storage
+            .list()?
+            .into_iter()
+            .filter(|resource| compiled.matches(resource))
+            .collect())
+    }
+
+    /// Bring client input into canonical form: drop undeclared fields if the definition
+    /// strips them, then apply transforms, coercion and the normalization of custom types
+    fn normalize(&self, resource: &mut Resource) {
+        if self.definition.unknown_fields == UnknownFields::Strip {
+            strip_unknown_fields(&self.definition.fields, &mut resource.data);
         }
+        normalize_fields(&self.definition.fields, &mut resource.data, &self.types);
+    }
 
-        Ok(())
+    /// Prepare a resource for returning it to a client
+    fn redact(&self, resource: Resource) -> Resource {
+        redact(&self.definition.fields, resource)
     }
 
     /// POST - Create a new resource
-    pub fn create(&mut self, resource: Resource) -> Result<Resource, MetaRestError> {
+    ///
+    /// Input is normalized and read-only fields cannot be set. Missing fields with a default
+    /// or generator are filled in before validation. The returned resource is the record as stored, including those
+    /// values, without write-only fields.
+    pub fn create(
+        &mut self,
+        caller: &CallerContext,
+        mut resource: Resource,
+    ) -> Result<Resource, MetaRestError> {
+        self.authorize(Access::Write, caller)?;
+        self.normalize(&mut resource);
+        apply_modifiers(&self.definition.fields, &mut resource, None)?;
+        apply_defaults(&self.definition.fields, &mut resource.data);
         self.validate(&resource)?;
-        self.storage.create(resource)
+        let created = self.storage.create(resource)?;
+        Ok(self.redact(created))
     }
 
     /// GET - Retrieve a specific resource
-    pub fn get(&self, id: &str) -> Result<Resource, MetaRestError> {
-        self.storage.get(id)
+    pub fn get(&self, caller: &CallerContext, id: &str) -> Result<Resource, MetaRestError> {
+        self.authorize(Access::Read, caller)?;
+        self.storage.get(id).map(|resource| self.redact(resource))
     }
 
     /// GET - List all resources
-    pub fn list(&self) -> Result<Vec<Resource>, MetaRestError> {
-        self.storage.list()
+    pub fn list(&self, caller: &CallerContext) -> Result<Vec<Resource>, MetaRestError> {
+        self.authorize(Access::Read, caller)?;
+        let resources = self.storage.list()?;
+        Ok(resources.into_iter().map(|r| self.redact(r)).collect())
     }
 
     /// GET - List resources with filters
-    pub fn list_filtered(&self, filters: &[Filter]) -> Result<Vec<Resource>, MetaRestError> {
-        self.storage.filter(filters)
+    pub fn list_filtered(
+        &self,
+        caller: &CallerContext,
+        filters: &[Filter],
+    ) -> Result<Vec<Resource>, MetaRestError> {
+        self.authorize(Access::Read, caller)?;
+        let leaves: Vec<&Filter> = filters.iter().collect();
+        self.check_query(&leaves, &[])?;
+        let resources = match self.uses_comparators(&leaves, &[]) {
+            true => self.filter_in_memory(&FilterExpr::from(filters))?,
+            false => self.storage.filter(filters)?,
+        };
+        Ok(resources.into_iter().map(|r| self.redact(r)).collect())
+    }
+
+    /// GET - List resources matching a boolean filter expression
+    pub fn list_where(
+        &self,
+        caller: &CallerContext,
+        expr: &FilterExpr,
+    ) -> Result<Vec<Resource>, MetaRestError> {
+        self.authorize(Access::Read, caller)?;
+        let leaves = expr.leaves();
+        self.check_query(&leaves, &[])?;
+        let resources = match self.uses_comparators(&leaves, &[]) {
+            true => self.filter_in_memory(expr)?,
+            false => self.storage.filter_expr(expr)?,
+        };
+        Ok(resources.into_iter().map(|r| self.redact(r)).collect())
+    }
+
+    /// GET - List resources with filters, sorting and pagination
+    pub fn query(&self, caller: &CallerContext, query: &ListQuery) -> Result<Page, MetaRestError> {
+        self.authorize(Access::Read, caller)?;
+        let leaves = query.filter.leaves();
+        self.check_query(&leaves, &query.sort)?;
+        let page = match self.uses_comparators(&leaves, &query.sort) {
+            true => query.paginate_with(
+                self.filter_in_memory(&query.filter)?,
+                Some(&self.comparators),
+            ),
+            false => self.storage.query(query)?,
+        };
+        Ok(Page {
+            items: page.items.into_iter().map(|r| self.redact(r)).collect(),
+            ..page
+        })
+    }
+
+    /// GET - List resources page by page using continuation tokens
+    pub fn query_cursor(
+        &self,
+        caller: &CallerContext,
+        query: &CursorQuery,
+    ) -> Result<CursorPage, MetaRestError> {
+        self.authorize(Access::Read, caller)?;
+        let leaves = query.filter.leaves();
+        self.check_query(&leaves, &query.sort)?;
+        let page = match self.uses_comparators(&leaves, &query.sort) {
+            true => query.paginate_with(
+                self.filter_in_memory(&query.filter)?,
+                Some(&self.comparators),
+            )?,
+            false => self.storage.query_cursor(query)?,
+        };
+        Ok(CursorPage {
+            items: page.items.into_iter().map(|r| self.redact(r)).collect(),
+            ..page
+        })
     }
 
     /// PUT - Update a resource
-    pub fn update(&mut self, id: &str, resource: Resource) -> Result<Resource, MetaRestError> {
+    ///
+    /// Read-only and immutable fields may only repeat their stored values. Those fields,
+    /// and write-only fields, keep their stored values when missing from the update.
+    pub fn update(
+        &mut self,
+        caller: &CallerContext,
+        id: &str,
+        mut resource: Resource,
+    ) -> Result<Resource, MetaRestError> {
+        self.authorize(Access::Write, caller)?;
+        let existing = self.storage.get(id)?;
+        self.normalize(&mut resource);
+        apply_modifiers(&self.definition.fields, &mut resource, Some(&existing))?;
         self.validate(&resource)?;
-        self.storage.update(id, resource)
+        let updated = self.storage.update(id, resource)?;
+        Ok(self.redact(updated))
     }
 
     /// DELETE - Delete a resource
-    pub fn delete(&mut self, id: &str) -> Result<(), MetaRestError> {
+    pub fn delete(&mut self, caller: &CallerContext, id: &str) -> Result<(), MetaRestError> {
+        self.authorize(Access::Write, caller)?;
         self.storage.delete(id)
     }
 
@@ -376,34 +702,66 @@ mod tests {
                 Field {
                     name: "name".to_string(),
                     field_type: "string".to_string(),
-                    required: true,
+                    required: Some(true),
                     validation: Some(ValidationRule {
                         min: Some(3.0),
                         max: Some(50.0),
-                        pattern: None,
+                        ..Default::default()
                     }),
+                    ..Default::default()
                 },
                 Field {
                     name: "age".to_string(),
                     field_type: "number".to_string(),
-                    required: false,
+                    required: Some(false),
                     validation: Some(ValidationRule {
                         min: Some(0.0),
                         max: Some(150.0),
-                        pattern: None,
+                        ..Default::default()
                     }),
+                    ..Default::default()
                 },
                 Field {
                     name: "email".to_string(),
                     field_type: "string".to_string(),
-                    required: true,
-                    validation: None,
+                    required: Some(true),
+                    ..Default::default()
                 },
             ],
             security: Some(SecurityPolicy {
                 require_auth: true,
                 allowed_roles: Some(vec!["admin".to_string(), "user".to_string()]),
+                read: None,
+                write: Some(AccessRule {
+                    require_auth: None,
+                    allowed_roles: Some(vec!["admin".to_string()]),
+                }),
             }),
+            ..Default::default()
+        }
+    }
+
+    fn admin() -> CallerContext {
+        CallerContext::authenticated("root", &["admin"])
+    }
+
+    /// A resource with the given id and JSON data
+    fn resource(id: &str, data: serde_json::Value) -> Resource {
+        Resource {
+            id: id.to_string(),
+            data: serde_json::from_value(data).unwrap(),
+        }
+    }
+
+    /// Field and rule of each violation of a failed operation
+    fn violations<T: fmt::Debug>(result: Result<T, MetaRestError>) -> Vec<(String, RuleKind)> {
+        match result {
+            Err(MetaRestError::ValidationError(errors)) => errors
+                .violations
+                .into_iter()
+                .map(|v| (v.field, v.rule))
+                .collect(),
+            other => panic!("Expected ValidationError, got {:?}", other),
         }
     }
 
@@ -441,10 +799,10 @@ mod tests {
     fn test_create_resource() {
         let def = create_test_definition();
         let storage = InMemoryStorage::new();
-        let mut manager = ResourceManager::new(def, storage);
+        let mut manager = ResourceManager::new(def, storage).unwrap();
 
         let resource = create_test_resource("1", "John Doe", 30.0, "john@example.com");
-        let result = manager.create(resource.clone());
+        let result = manager.create(&admin(), resource.clone());
         assert!(result.is_ok());
 
         let created = result.unwrap();
@@ -455,12 +813,12 @@ mod tests {
     fn test_get_resource() {
         let def = create_test_definition();
         let storage = InMemoryStorage::new();
-        let mut manager = ResourceManager::new(def, storage);
+        let mut manager = ResourceManager::new(def, storage).unwrap();
 
         let resource = create_test_resource("1", "John Doe", 30.0, "john@example.com");
-        manager.create(resource).unwrap();
+        manager.create(&admin(), resource).unwrap();
 
-        let result = manager.get("1");
+        let result = manager.get(&admin(), "1");
         assert!(result.is_ok());
 
         let retrieved = result.unwrap();
@@ -475,9 +833,9 @@ mod tests {
     fn test_get_nonexistent_resource() {
         let def = create_test_definition();
         let storage = InMemoryStorage::new();
-        let manager = ResourceManager::new(def, storage);
+        let manager = ResourceManager::new(def, storage).unwrap();
 
-        let result = manager.get("999");
+        let result = manager.get(&admin(), "999");
         assert!(result.is_err());
         match result {
             Err(MetaRestError::NotFound(_)) => (),
@@ -489,26 +847,22 @@ mod tests {
     fn test_list_resources() {
         let def = create_test_definition();
         let storage = InMemoryStorage::new();
-        let mut manager = ResourceManager::new(def, storage);
+        let mut manager = ResourceManager::new(def, storage).unwrap();
 
         manager
-            .create(create_test_resource(
-                "1",
-                "John Doe",
-                30.0,
-                "john@example.com",
-            ))
+            .create(
+                &admin(),
+                create_test_resource("1", "John Doe", 30.0, "john@example.com"),
+            )
             .unwrap();
         manager
-            .create(create_test_resource(
-                "2",
-                "Jane Smith",
-                25.0,
-                "jane@example.com",
-            ))
+            .create(
+                &admin(),
+                create_test_resource("2", "Jane Smith", 25.0, "jane@example.com"),
+            )
             .unwrap();
 
-        let result = manager.list();
+        let result = manager.list(&admin());
         assert!(result.is_ok());
 
         let resources = result.unwrap();
@@ -519,16 +873,16 @@ mod tests {
     fn test_update_resource() {
         let def = create_test_definition();
         let storage = InMemoryStorage::new();
-        let mut manager = ResourceManager::new(def, storage);
+        let mut manager = ResourceManager::new(def, storage).unwrap();
 
         let resource = create_test_resource("1", "John Doe", 30.0, "john@example.com");
-        manager.create(resource).unwrap();
+        manager.create(&admin(), resource).unwrap();
 
         let updated = create_test_resource("1", "John Smith", 31.0, "john.smith@example.com");
-        let result = manager.update("1", updated);
+        let result = manager.update(&admin(), "1", updated);
         assert!(result.is_ok());
 
-        let retrieved = manager.get("1").unwrap();
+        let retrieved = manager.get(&admin(), "1").unwrap();
         assert_eq!(
             retrieved.data.get("name").unwrap().as_str().unwrap(),
             "John Smith"
@@ -540,15 +894,15 @@ mod tests {
     fn test_delete_resource() {
         let def = create_test_definition();
         let storage = InMemoryStorage::new();
-        let mut manager = ResourceManager::new(def, storage);
+        let mut manager = ResourceManager::new(def, storage).unwrap();
 
         let resource = create_test_resource("1", "John Doe", 30.0, "john@example.com");
-        manager.create(resource).unwrap();
+        manager.create(&admin(), resource).unwrap();
 
-        let result = manager.delete("1");
+        let result = manager.delete(&admin(), "1");
         assert!(result.is_ok());
 
-        let get_result = manager.get("1");
+        let get_result = manager.get(&admin(), "1");
         assert!(get_result.is_err());
     }
 
@@ -556,7 +910,7 @@ mod tests {
     fn test_validation_required_fields() {
         let def = create_test_definition();
         let storage = InMemoryStorage::new();
-        let mut manager = ResourceManager::new(def, storage);
+        let mut manager = ResourceManager::new(def, storage).unwrap();
 
         let mut data = HashMap::new();
         data.insert(
@@ -570,11 +924,13 @@ mod tests {
             data,
         };
 
-        let result = manager.create(resource);
+        let result = manager.create(&admin(), resource);
         assert!(result.is_err());
         match result {
-            Err(MetaRestError::ValidationError(msg)) => {
-                assert!(msg.contains("email"));
+            Err(MetaRestError::ValidationError(errors)) => {
+                assert_eq!(errors.violations.len(), 1);
+                assert_eq!(errors.violations[0].field, "email");
+                assert_eq!(errors.violations[0].rule, RuleKind::Required);
             }
             _ => panic!("Expected ValidationError"),
         }
@@ -584,7 +940,7 @@ mod tests {
     fn test_validation_field_type() {
         let def = create_test_definition();
         let storage = InMemoryStorage::new();
-        let mut manager = ResourceManager::new(def, storage);
+        let mut manager = ResourceManager::new(def, storage).unwrap();
 
         let mut data = HashMap::new();
         data.insert(
@@ -605,11 +961,12 @@ mod tests {
             data,
         };
 
-        let result = manager.create(resource);
+        let result = manager.create(&admin(), resource);
         assert!(result.is_err());
         match result {
-            Err(MetaRestError::ValidationError(msg)) => {
-                assert!(msg.contains("age"));
+            Err(MetaRestError::ValidationError(errors)) => {
+                assert_eq!(errors.violations[0].field, "age");
+                assert_eq!(errors.violations[0].rule, RuleKind::Type);
             }
             _ => panic!("Expected ValidationError"),
         }
@@ -619,16 +976,16 @@ mod tests {
     fn test_validation_min_max_number() {
         let def = create_test_definition();
         let storage = InMemoryStorage::new();
-        let mut manager = ResourceManager::new(def, storage);
+        let mut manager = ResourceManager::new(def, storage).unwrap();
 
         // Test minimum
         let resource = create_test_resource("1", "John Doe", -5.0, "john@example.com");
-        let result = manager.create(resource);
+        let result = manager.create(&admin(), resource);
         assert!(result.is_err());
 
         // Test maximum
         let resource = create_test_resource("2", "Jane Doe", 200.0, "jane@example.com");
-        let result = manager.create(resource);
+        let result = manager.create(&admin(), resource);
         assert!(result.is_err());
     }
 
@@ -636,17 +993,17 @@ mod tests {
     fn test_validation_min_max_string() {
         let def = create_test_definition();
         let storage = InMemoryStorage::new();
-        let mut manager = ResourceManager::new(def, storage);
+        let mut manager = ResourceManager::new(def, storage).unwrap();
 
         // Test minimum length
         let resource = create_test_resource("1", "Jo", 30.0, "jo@example.com");
-        let result = manager.create(resource);
+        let result = manager.create(&admin(), resource);
         assert!(result.is_err());
 
         // Test maximum length
         let long_name = "A".repeat(100);
         let resource = create_test_resource("2", &long_name, 30.0, "test@example.com");
-        let result = manager.create(resource);
+        let result = manager.create(&admin(), resource);
         assert!(result.is_err());
     }
 
@@ -654,41 +1011,35 @@ mod tests {
     fn test_filter_resources() {
         let def = create_test_definition();
         let storage = InMemoryStorage::new();
-        let mut manager = ResourceManager::new(def, storage);
+        let mut manager = ResourceManager::new(def, storage).unwrap();
 
         manager
-            .create(create_test_resource(
-                "1",
-                "John Doe",
-                30.0,
-                "john@example.com",
-            ))
+            .create(
+                &admin(),
+                create_test_resource("1", "John Doe", 30.0, "john@example.com"),
+            )
             .unwrap();
         manager
-            .create(create_test_resource(
-                "2",
-                "Jane Smith",
-                25.0,
-                "jane@example.com",
-            ))
+            .create(
+                &admin(),
+                create_test_resource("2", "Jane Smith", 25.0, "jane@example.com"),
+            )
             .unwrap();
         manager
-            .create(create_test_resource(
-                "3",
-                "Bob Jones",
-                35.0,
-                "bob@example.com",
-            ))
+            .create(
+                &admin(),
+                create_test_resource("3", "Bob Jones", 35.0, "bob@example.com"),
+            )
             .unwrap();
 
         // Filter by age greater than 28
         let filters = vec![Filter {
             field: "age".to_string(),
-            operator: "gt".to_string(),
+            operator: FilterOperator::Gt,
             value: serde_json::Value::Number(serde_json::Number::from_f64(28.0).unwrap()),
         }];
 
-        let result = manager.list_filtered(&filters);
+        let result = manager.list_filtered(&admin(), &filters);
         assert!(result.is_ok());
 
         let filtered = result.unwrap();
@@ -699,32 +1050,28 @@ mod tests {
     fn test_filter_equals() {
         let def = create_test_definition();
         let storage = InMemoryStorage::new();
-        let mut manager = ResourceManager::new(def, storage);
+        let mut manager = ResourceManager::new(def, storage).unwrap();
 
         manager
-            .create(create_test_resource(
-                "1",
-                "John Doe",
-                30.0,
-                "john@example.com",
-            ))
+            .create(
+                &admin(),
+                create_test_resource("1", "John Doe", 30.0, "john@example.com"),
+            )
             .unwrap();
         manager
-            .create(create_test_resource(
-                "2",
-                "Jane Smith",
-                25.0,
-                "jane@example.com",
-            ))
+            .create(
+                &admin(),
+                create_test_resource("2", "Jane Smith", 25.0, "jane@example.com"),
+            )
             .unwrap();
 
         let filters = vec![Filter {
             field: "name".to_string(),
-            operator: "eq".to_string(),
+            operator: FilterOperator::Eq,
             value: serde_json::Value::String("John Doe".to_string()),
         }];
 
-        let result = manager.list_filtered(&filters);
+        let result = manager.list_filtered(&admin(), &filters);
         assert!(result.is_ok());
 
         let filtered = result.unwrap();
@@ -736,40 +1083,34 @@ mod tests {
     fn test_filter_contains() {
         let def = create_test_definition();
         let storage = InMemoryStorage::new();
-        let mut manager = ResourceManager::new(def, storage);
+        let mut manager = ResourceManager::new(def, storage).unwrap();
 
         manager
-            .create(create_test_resource(
-                "1",
-                "John Doe",
-                30.0,
-                "john@example.com",
-            ))
+            .create(
+                &admin(),
+                create_test_resource("1", "John Doe", 30.0, "john@example.com"),
+            )
             .unwrap();
         manager
-            .create(create_test_resource(
-                "2",
-                "Jane Doe",
-                25.0,
-                "jane@example.com",
-            ))
+            .create(
+                &admin(),
+                create_test_resource("2", "Jane Doe", 25.0, "jane@example.com"),
+            )
             .unwrap();
         manager
-            .create(create_test_resource(
-                "3",
-                "Bob Smith",
-                35.0,
-                "bob@example.com",
-            ))
+            .create(
+                &admin(),
+                create_test_resource("3", "Bob Smith", 35.0, "bob@example.com"),
+            )
             .unwrap();
 
         let filters = vec![Filter {
             field: "name".to_string(),
-            operator: "contains".to_string(),
+            operator: FilterOperator::Contains,
             value: serde_json::Value::String("Doe".to_string()),
         }];
 
-        let result = manager.list_filtered(&filters);
+        let result = manager.list_filtered(&admin(), &filters);
         assert!(result.is_ok());
 
         let filtered = result.unwrap();
@@ -780,50 +1121,1461 @@ mod tests {
     fn test_multiple_filters() {
         let def = create_test_definition();
         let storage = InMemoryStorage::new();
-        let mut manager = ResourceManager::new(def, storage);
+        let mut manager = ResourceManager::new(def, storage).unwrap();
 
         manager
-            .create(create_test_resource(
-                "1",
-                "John Doe",
-                30.0,
-                "john@example.com",
-            ))
+            .create(
+                &admin(),
+                create_test_resource("1", "John Doe", 30.0, "john@example.com"),
+            )
             .unwrap();
         manager
-            .create(create_test_resource(
-                "2",
-                "Jane Doe",
-                25.0,
-                "jane@example.com",
-            ))
+            .create(
+                &admin(),
+                create_test_resource("2", "Jane Doe", 25.0, "jane@example.com"),
+            )
             .unwrap();
         manager
-            .create(create_test_resource(
-                "3",
-                "Bob Doe",
-                35.0,
-                "bob@example.com",
-            ))
+            .create(
+                &admin(),
+                create_test_resource("3", "Bob Doe", 35.0, "bob@example.com"),
+            )
             .unwrap();
 
         let filters = vec![
             Filter {
                 field: "name".to_string(),
-                operator: "contains".to_string(),
+                operator: FilterOperator::Contains,
                 value: serde_json::Value::String("Doe".to_string()),
             },
             Filter {
                 field: "age".to_string(),
-                operator: "gt".to_string(),
+                operator: FilterOperator::Gt,
                 value: serde_json::Value::Number(serde_json::Number::from_f64(28.0).unwrap()),
             },
         ];
 
-        let result = manager.list_filtered(&filters);
+        let result = manager.list_filtered(&admin(), &filters);
         assert!(result.is_ok());
 
         let filtered = result.unwrap();
         assert_eq!(filtered.len(), 2); // John and Bob, not Jane (age 25)
     }
+
+    #[test]
+    fn test_security_policy_enforced() {
+        let def = create_test_definition();
+        let storage = InMemoryStorage::new();
+        let mut manager = ResourceManager::new(def, storage).unwrap();
+        let user = CallerContext::authenticated("jane", &["user"]);
+        let guest = CallerContext::authenticated("bob", &["guest"]);
+
+        let resource = create_test_resource("1", "John Doe", 30.0, "john@example.com");
+        match manager.create(&CallerContext::anonymous(), resource.clone()) {
+            Err(MetaRestError::Unauthorized(_)) => (),
+            _ => panic!("Expected Unauthorized error"),
+        }
+        // Users may read but only admins may write
+        match manager.create(&user, resource.clone()) {
+            Err(MetaRestError::Forbidden(_)) => (),
+            _ => panic!("Expected Forbidden error"),
+        }
+        manager.create(&admin(), resource).unwrap();
+
+        assert!(manager.get(&user, "1").is_ok());
+        assert!(manager.list(&user).is_ok());
+        assert!(matches!(
+            manager.get(&guest, "1"),
+            Err(MetaRestError::Forbidden(_))
+        ));
+        assert!(matches!(
+            manager.delete(&user, "1"),
+            Err(MetaRestError::Forbidden(_))
+        ));
+    }
+
+    #[test]
+    fn test_validation_pattern() {
+        let mut def = create_test_definition();
+        def.fields[2].validation = Some(ValidationRule {
+            pattern: Some(r"^[^@\s]+@[^@\s]+\.[a-z]+$".to_string()),
+            ..Default::default()
+        });
+        let mut manager = ResourceManager::new(def, InMemoryStorage::new()).unwrap();
+
+        let resource = create_test_resource("1", "John Doe", 30.0, "john@example.com");
+        assert!(manager.create(&admin(), resource).is_ok());
+
+        let resource = create_test_resource("2", "Jane Doe", 30.0, "not-an-email");
+        match manager.create(&admin(), resource) {
+            Err(MetaRestError::ValidationError(errors)) => {
+                let violation = &errors.violations[0];
+                assert_eq!(violation.field, "email");
+                assert_eq!(violation.rule, RuleKind::Pattern);
+                assert!(violation.message.contains("pattern"));
+            }
+            _ => panic!("Expected ValidationError"),
+        }
+    }
+
+    #[test]
+    fn test_invalid_pattern_rejected() {
+        let mut def = create_test_definition();
+        def.fields[0].validation = Some(ValidationRule {
+            pattern: Some("[a-z".to_string()),
+            ..Default::default()
+        });
+
+        match ResourceManager::new(def, InMemoryStorage::new()) {
+            Err(MetaRestError::InvalidDefinition(msg)) => assert!(msg.contains("name")),
+            _ => panic!("Expected InvalidDefinition error"),
+        }
+    }
+
+    #[test]
+    fn test_validation_collects_all_violations() {
+        let def = create_test_definition();
+        let manager = ResourceManager::new(def, InMemoryStorage::new()).unwrap();
+
+        let mut data = HashMap::new();
+        data.insert("name".to_string(), serde_json::json!("Jo"));
+        data.insert("age".to_string(), serde_json::json!(200));
+        let resource = Resource {
+            id: "1".to_string(),
+            data,
+        };
+
+        match manager.validate(&resource) {
+            Err(MetaRestError::ValidationError(errors)) => {
+                let fields: Vec<(&str, RuleKind)> = errors
+                    .violations
+                    .iter()
+                    .map(|v| (v.field.as_str(), v.rule))
+                    .collect();
+                assert_eq!(
+                    fields,
+                    vec![
+                        ("name", RuleKind::Min),
+                        ("age", RuleKind::Max),
+                        ("email", RuleKind::Required)
+                    ]
+                );
+                assert_eq!(errors.violations[1].actual, Some(serde_json::json!(200)));
+            }
+            _ => panic!("Expected ValidationError"),
+        }
+    }
+
+    #[test]
+    fn test_query_sorted_page() {
+        let def = create_test_definition();
+        let storage = InMemoryStorage::new();
+        let mut manager = ResourceManager::new(def, storage).unwrap();
+
+        for (id, name, age) in [
+            ("1", "John Doe", 30.0),
+            ("2", "Jane Doe", 25.0),
+            ("3", "Bob Doe", 35.0),
+            ("4", "Ann Smith", 45.0),
+        ] {
+            let email = format!("{}@example.com", id);
+            manager
+                .create(&admin(), create_test_resource(id, name, age, &email))
+                .unwrap();
+        }
+
+        let query = ListQuery::new()
+            .filter(Filter {
+                field: "name".to_string(),
+                operator: FilterOperator::Contains,
+                value: serde_json::Value::String("Doe".to_string()),
+            })
+            .sort(SortKey::desc("age"))
+            .limit(2);
+
+        let page = manager.query(&admin(), &query).unwrap();
+        assert_eq!(page.total, 3);
+        let ids: Vec<&str> = page.items.iter().map(|r| r.id.as_str()).collect();
+        assert_eq!(ids, vec!["3", "1"]);
+    }
+
+    #[test]
+    fn test_filter_expression() {
+        let def = create_test_definition();
+        let storage = InMemoryStorage::new();
+        let mut manager = ResourceManager::new(def, storage).unwrap();
+
+        for (id, name, age) in [
+            ("1", "John Doe", 30.0),
+            ("2", "Jane Doe", 25.0),
+            ("3", "Bob Smith", 35.0),
+        ] {
+            let email = format!("{}@example.com", id);
+            manager
+                .create(&admin(), create_test_resource(id, name, age, &email))
+                .unwrap();
+        }
+
+        // age < 28 OR NOT name contains "Doe"
+        let expr: FilterExpr = serde_json::from_value(serde_json::json!({
+            "or": [
+                { "field": "age", "operator": "lt", "value": 28 },
+                { "not": { "field": "name", "operator": "contains", "value": "Doe" } }
+            ]
+        }))
+        .unwrap();
+
+        let result = manager.list_where(&admin(), &expr).unwrap();
+        let ids: Vec<&str> = result.iter().map(|r| r.id.as_str()).collect();
+        assert_eq!(ids, vec!["2", "3"]);
+    }
+
+    #[test]
+    fn test_invalid_filter_rejected() {
+        let def = create_test_definition();
+        let manager = ResourceManager::new(def, InMemoryStorage::new()).unwrap();
+
+        let filters = vec![Filter::new("age", FilterOperator::Between, 30)];
+        match manager.list_filtered(&admin(), &filters) {
+            Err(MetaRestError::ValidationError(errors)) => {
+                assert_eq!(errors.violations[0].field, "age");
+            }
+            _ => panic!("Expected ValidationError"),
+        }
+    }
+
+    fn create_address_manager() -> ResourceManager<InMemoryStorage> {
+        let def: ResourceDefinition = serde_json::from_value(serde_json::json!({
+            "name": "customers",
+            "fields": [{
+                "name": "address",
+                "field_type": "object",
+                "required": true,
+                "fields": [
+                    { "name": "city", "field_type": "string", "required": true },
+                    {
+                        "name": "zip",
+                        "field_type": "string",
+                        "required": false,
+                        "validation": { "pattern": "^[0-9]{5}$" }
+                    }
+                ]
+            }]
+        }))
+        .unwrap();
+        ResourceManager::new(def, InMemoryStorage::new()).unwrap()
+    }
+
+    fn create_customer(id: &str, address: serde_json::Value) -> Resource {
+        Resource {
+            id: id.to_string(),
+            data: HashMap::from([("address".to_string(), address)]),
+        }
+    }
+
+    #[test]
+    fn test_nested_object_validation() {
+        let mut manager = create_address_manager();
+        let resource =
+            create_customer("1", serde_json::json!({ "city": "Berlin", "zip": "10115" }));
+        assert!(manager.create(&admin(), resource).is_ok());
+
+        let resource = create_customer("2", serde_json::json!({ "zip": "ABC" }));
+        assert_eq!(
+            violations(manager.validate(&resource)),
+            vec![
+                ("address.city".to_string(), RuleKind::Required),
+                ("address.zip".to_string(), RuleKind::Pattern)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_filter_by_dotted_path() {
+        let mut manager = create_address_manager();
+        for (id, city) in [("1", "Berlin"), ("2", "Paris"), ("3", "Bern")] {
+            let resource = create_customer(id, serde_json::json!({ "city": city }));
+            manager.create(&admin(), resource).unwrap();
+        }
+
+        let filters = vec![Filter::new(
+            "address.city",
+            FilterOperator::StartsWith,
+            "Ber",
+        )];
+        let mut ids: Vec<String> = manager
+            .list_filtered(&admin(), &filters)
+            .unwrap()
+            .into_iter()
+            .map(|r| r.id)
+            .collect();
+        ids.sort();
+        assert_eq!(ids, vec!["1", "3"]);
+
+        let query = ListQuery::new().sort(SortKey::desc("address.city"));
+        let page = manager.query(&admin(), &query).unwrap();
+        let ids: Vec<&str> = page.items.iter().map(|r| r.id.as_str()).collect();
+        assert_eq!(ids, vec!["2", "3", "1"]);
+
+        assert_eq!(
+            manager.definition().field("address.city").unwrap().name,
+            "city"
+        );
+        assert!(manager.definition().field("address.country").is_none());
+    }
+
+    #[test]
+    fn test_nested_fields_require_object_type() {
+        let mut def = create_test_definition();
+        def.fields[0].fields = Some(vec![]);
+
+        match ResourceManager::new(def, InMemoryStorage::new()) {
+            Err(MetaRestError::InvalidDefinition(msg)) => assert!(msg.contains("name")),
+            _ => panic!("Expected InvalidDefinition error"),
+        }
+    }
+
+    #[test]
+    fn test_array_items_validation() {
+        let def: ResourceDefinition = serde_json::from_value(serde_json::json!({
+            "name": "posts",
+            "fields": [
+                {
+                    "name": "tags",
+                    "field_type": "array",
+                    "required": true,
+                    "items": { "field_type": "string", "validation": { "pattern": "^[a-z]+$" } },
+                    "validation": { "min_items": 1, "max_items": 4, "unique_items": true }
+                },
+                {
+                    "name": "authors",
+                    "field_type": "array",
+                    "required": false,
+                    "items": {
+                        "field_type": "object",
+                        "fields": [{ "name": "name", "field_type": "string", "required": true }]
+                    }
+                }
+            ]
+        }))
+        .unwrap();
+        let manager = ResourceManager::new(def, InMemoryStorage::new()).unwrap();
+
+        let valid = resource(
+            "1",
+            serde_json::json!({
+                "tags": ["rust", "web"],
+                "authors": [{ "name": "Alice" }]
+            }),
+        );
+        assert!(manager.validate(&valid).is_ok());
+
+        let invalid = resource(
+            "1",
+            serde_json::json!({
+                "tags": ["rust", "web", 3, "Rust!", "rust"],
+                "authors": [{ "name": "Alice" }, {}]
+            }),
+        );
+        assert_eq!(
+            violations(manager.validate(&invalid)),
+            vec![
+                ("tags".to_string(), RuleKind::Max),
+                ("tags[4]".to_string(), RuleKind::Unique),
+                ("tags[2]".to_string(), RuleKind::Type),
+                ("tags[3]".to_string(), RuleKind::Pattern),
+                ("authors[1].name".to_string(), RuleKind::Required),
+            ]
+        );
+
+        let empty = resource("1", serde_json::json!({ "tags": [] }));
+        assert_eq!(
+            violations(manager.validate(&empty)),
+            vec![("tags".to_string(), RuleKind::Min)]
+        );
+    }
+
+    #[test]
+    fn test_items_require_array_type() {
+        let mut def = create_test_definition();
+        def.fields[0].items = Some(Box::new(Field {
+            field_type: "string".to_string(),
+            ..Default::default()
+        }));
+
+        match ResourceManager::new(def, InMemoryStorage::new()) {
+            Err(MetaRestError::InvalidDefinition(msg)) => assert!(msg.contains("item schema")),
+            _ => panic!("Expected InvalidDefinition error"),
+        }
+    }
+
+    #[test]
+    fn test_allowed_values() {
+        let def: ResourceDefinition = serde_json::from_value(serde_json::json!({
+            "name": "tickets",
+            "fields": [
+                {
+                    "name": "status",
+                    "field_type": "string",
+                    "required": true,
+                    "allowed_values": ["open", "closed"]
+                },
+                {
+                    "name": "labels",
+                    "field_type": "array",
+                    "required": false,
+                    "items": { "field_type": "string", "allowed_values": ["bug", "feature"] }
+                }
+            ]
+        }))
+        .unwrap();
+        let json = serde_json::to_value(&def).unwrap();
+        assert_eq!(
+            json["fields"][0]["allowed_values"],
+            serde_json::json!(["open", "closed"])
+        );
+
+        let mut manager = ResourceManager::new(def, InMemoryStorage::new()).unwrap();
+        let created = manager.create(
+            &admin(),
+            resource(
+                "1",
+                serde_json::json!({ "status": "open", "labels": ["bug"] }),
+            ),
+        );
+        assert!(created.is_ok());
+
+        let invalid = resource(
+            "2",
+            serde_json::json!({ "status": "pending", "labels": ["bug", "question"] }),
+        );
+        match manager.validate(&invalid) {
+            Err(MetaRestError::ValidationError(errors)) => {
+                let fields: Vec<_> = errors.violations.iter().map(|v| &v.field).collect();
+                assert_eq!(fields, vec!["status", "labels[1]"]);
+                assert!(errors.violations.iter().all(|v| v.rule == RuleKind::Enum));
+                assert_eq!(
+                    errors.violations[0].expected,
+                    Some(serde_json::json!(["open", "closed"]))
+                );
+            }
+            _ => panic!("Expected ValidationError"),
+        }
+
+        let filters = vec![Filter::new("status", FilterOperator::Eq, "open")];
+        assert_eq!(manager.list_filtered(&admin(), &filters).unwrap().len(), 1);
+
+        let expr = FilterExpr::from(Filter::new(
+            "status",
+            FilterOperator::In,
+            serde_json::json!(["open", "pending"]),
+        ))
+        .or(Filter::new("labels", FilterOperator::Contains, "question"));
+        match manager.list_where(&admin(), &expr) {
+            Err(MetaRestError::ValidationError(errors)) => {
+                let fields: Vec<_> = errors.violations.iter().map(|v| &v.field).collect();
+                assert_eq!(fields, vec!["status", "labels"]);
+            }
+            _ => panic!("Expected ValidationError"),
+        }
+    }
+
+    #[test]
+    fn test_formats_and_date_comparison() {
+        let def: ResourceDefinition = serde_json::from_value(serde_json::json!({
+            "name": "events",
+            "fields": [
+                {
+                    "name": "contact",
+                    "field_type": "string",
+                    "required": false,
+                    "validation": { "format": "email" }
+                },
+                {
+                    "name": "starts_at",
+                    "field_type": "string",
+                    "required": true,
+                    "validation": { "format": "date-time" }
+                },
+                { "name": "note", "field_type": "string" }
+            ]
+        }))
+        .unwrap();
+        let mut manager = ResourceManager::new(def, InMemoryStorage::new()).unwrap();
+
+        match manager.validate(&resource(
+            "0",
+            serde_json::json!({ "contact": "nobody", "starts_at": "tomorrow" }),
+        )) {
+            Err(MetaRestError::ValidationError(errors)) => {
+                assert_eq!(errors.violations.len(), 2);
+                assert!(errors.violations.iter().all(|v| v.rule == RuleKind::Format));
+                assert_eq!(errors.violations[1].expected, Some("date-time".into()));
+            }
+            _ => panic!("Expected ValidationError"),
+        }
+
+        for (id, starts_at, note) in [
+            ("1", "2024-03-01T09:00:00+02:00", "1999 recap"),
+            ("2", "2024-03-01T08:00:00Z", "2024-03-01"),
+            ("3", "2024-02-29T23:30:00-01:00", "Agenda"),
+        ] {
+            let data = serde_json::json!({ "starts_at": starts_at, "note": note });
+            manager.create(&admin(), resource(id, data)).unwrap();
+        }
+
+        // 07:00Z, 08:00Z and 00:30Z on March 1st respectively
+        let filters = vec![Filter::new(
+            "starts_at",
+            FilterOperator::Gt,
+            "2024-03-01T07:30:00Z",
+        )];
+        let found = manager.list_filtered(&admin(), &filters).unwrap();
+        let ids: Vec<&str> = found.iter().map(|r| r.id.as_str()).collect();
+        assert_eq!(ids, vec!["2"]);
+
+        let filters = vec![Filter::new("starts_at", FilterOperator::Gte, "2024-03-01")];
+        assert_eq!(manager.list_filtered(&admin(), &filters).unwrap().len(), 3);
+
+        let query = ListQuery::new().sort(SortKey::asc("starts_at"));
+        let page = manager.query(&admin(), &query).unwrap();
+        let ids: Vec<&str> = page.items.iter().map(|r| r.id.as_str()).collect();
+        assert_eq!(ids, vec!["3", "1", "2"]);
+
+        // Strings without a date format keep their lexical order, even if they look like dates
+        let query = ListQuery::new().sort(SortKey::asc("note"));
+        let page = manager.query(&admin(), &query).unwrap();
+        let ids: Vec<&str> = page.items.iter().map(|r| r.id.as_str()).collect();
+        assert_eq!(ids, vec!["1", "2", "3"]);
+    }
+
+    #[test]
+    fn test_integer_and_decimal_fields() {
+        let def: ResourceDefinition = serde_json::from_value(serde_json::json!({
+            "name": "orders",
+            "fields": [
+                {
+                    "name": "quantity",
+                    "field_type": "integer",
+                    "required": true,
+                    "validation": { "min": 1, "max": 100 }
+                },
+                {
+                    "name": "price",
+                    "field_type": "decimal",
+                    "required": true,
+                    "validation": { "scale": 2, "precision": 6, "multiple_of": 0.05 }
+                },
+                { "name": "serial", "field_type": "integer", "required": false }
+            ]
+        }))
+        .unwrap();
+        let mut manager = ResourceManager::new(def, InMemoryStorage::new()).unwrap();
+
+        // 0.3 is not a multiple of 0.05 in floating point, but is exactly
+        let valid = resource("1", serde_json::json!({ "quantity": 3, "price": 0.3 }));
+        assert!(manager.validate(&valid).is_ok());
+
+        let invalid = resource(
+            "2",
+            serde_json::json!({ "quantity": 2.5, "price": 12345.675, "serial": "7" }),
+        );
+        assert_eq!(
+            violations(manager.validate(&invalid)),
+            vec![
+                ("quantity".to_string(), RuleKind::Type),
+                ("price".to_string(), RuleKind::Precision),
+                ("price".to_string(), RuleKind::Precision),
+                ("price".to_string(), RuleKind::MultipleOf),
+                ("serial".to_string(), RuleKind::Type),
+            ]
+        );
+
+        let invalid = resource("3", serde_json::json!({ "quantity": 0, "price": 9999.99 }));
+        assert_eq!(
+            violations(manager.validate(&invalid)),
+            vec![
+                ("quantity".to_string(), RuleKind::Min),
+                ("price".to_string(), RuleKind::MultipleOf),
+            ]
+        );
+
+        // Serials that are equal as f64
+        for (id, serial) in [
+            ("a", 9_007_199_254_740_992u64),
+            ("b", 9_007_199_254_740_993),
+        ] {
+            let data = serde_json::json!({ "quantity": 1, "price": 1.5, "serial": serial });
+            manager.create(&admin(), resource(id, data)).unwrap();
+        }
+        let filters = vec![Filter::new(
+            "serial",
+            FilterOperator::Gt,
+            9_007_199_254_740_992u64,
+        )];
+        let found = manager.list_filtered(&admin(), &filters).unwrap();
+        let ids: Vec<&str> = found.iter().map(|r| r.id.as_str()).collect();
+        assert_eq!(ids, vec!["b"]);
+    }
+
+    #[test]
+    fn test_decimal_rules_require_decimal_type() {
+        let mut def = create_test_definition();
+        def.fields[1].validation = Some(ValidationRule {
+            scale: Some(2),
+            ..Default::default()
+        });
+        match ResourceManager::new(def, InMemoryStorage::new()) {
+            Err(MetaRestError::InvalidDefinition(msg)) => assert!(msg.contains("age")),
+            _ => panic!("Expected InvalidDefinition error"),
+        }
+
+        let mut def = create_test_definition();
+        def.fields[0].validation = Some(ValidationRule {
+            multiple_of: Some(2.0),
+            ..Default::default()
+        });
+        match ResourceManager::new(def, InMemoryStorage::new()) {
+            Err(MetaRestError::InvalidDefinition(msg)) => assert!(msg.contains("name")),
+            _ => panic!("Expected InvalidDefinition error"),
+        }
+    }
+
+    #[test]
+    fn test_create_fills_in_defaults() {
+        let def: ResourceDefinition = serde_json::from_value(serde_json::json!({
+            "name": "accounts",
+            "fields": [
+                { "name": "name", "field_type": "string", "required": true },
+                { "name": "active", "field_type": "boolean", "required": true, "default": true },
+                { "name": "token", "field_type": "string", "required": false, "generate": "uuid" },
+                {
+                    "name": "created_at",
+                    "field_type": "string",
+                    "required": true,
+                    "generate": "now",
+                    "validation": { "format": "date-time" }
+                },
+                {
+                    "name": "settings",
+                    "field_type": "object",
+                    "required": false,
+                    "fields": [
+                        { "name": "theme", "field_type": "string", "required": true, "default": "light" }
+                    ]
+                }
+            ]
+        }))
+        .unwrap();
+        let mut manager = ResourceManager::new(def, InMemoryStorage::new()).unwrap();
+
+        let resource = Resource {
+            id: "1".to_string(),
+            data: serde_json::from_value(serde_json::json!({
+                "name": "Alice",
+                "active": false,
+                "settings": {}
+            }))
+            .unwrap(),
+        };
+        let created = manager.create(&admin(), resource).unwrap();
+        assert_eq!(created.data["active"], false);
+        assert_eq!(created.data["settings"]["theme"], "light");
+        assert!(Format::Uuid.is_valid(created.data["token"].as_str().unwrap()));
+        assert!(created.data.contains_key("created_at"));
+
+        let stored = manager.get(&admin(), "1").unwrap();
+        assert_eq!(stored.data, created.data);
+
+        let resource = Resource {
+            id: "2".to_string(),
+            data: HashMap::from([("name".to_string(), serde_json::json!("Bob"))]),
+        };
+        let created = manager.create(&admin(), resource).unwrap();
+        assert_eq!(created.data["active"], true);
+        assert!(!created.data.contains_key("settings"));
+    }
+
+    #[test]
+    fn test_invalid_default_rejected() {
+        let mut def = create_test_definition();
+        def.fields[1].default = Some(serde_json::json!(200));
+
+        match ResourceManager::new(def, InMemoryStorage::new()) {
+            Err(MetaRestError::InvalidDefinition(msg)) => {
+                assert!(msg.contains("'age' has an invalid default"))
+            }
+            _ => panic!("Expected InvalidDefinition error"),
+        }
+    }
+
+    fn create_account_manager() -> ResourceManager<InMemoryStorage> {
+        let def: ResourceDefinition = serde_json::from_value(serde_json::json!({
+            "name": "accounts",
+            "fields": [
+                { "name": "username", "field_type": "string", "required": true, "immutable": true },
+                { "name": "password", "field_type": "string", "required": true, "write_only": true },
+                {
+                    "name": "created_at",
+                    "field_type": "string",
+                    "required": true,
+                    "read_only": true,
+                    "immutable": true,
+                    "generate": "now"
+                },
+                {
+                    "name": "updated_at",
+                    "field_type": "string",
+                    "read_only": true,
+                    "generate": "now"
+                },
+                { "name": "bio", "field_type": "string", "required": false }
+            ]
+        }))
+        .unwrap();
+        ResourceManager::new(def, InMemoryStorage::new()).unwrap()
+    }
+
+    #[test]
+    fn test_read_only_and_write_only_fields() {
+        let mut manager = create_account_manager();
+
+        let result = manager.create(
+            &admin(),
+            resource(
+                "1",
+                serde_json::json!({
+                    "username": "alice",
+                    "password": "secret",
+                    "created_at": "2020-01-01T00:00:00Z"
+                }),
+            ),
+        );
+        assert_eq!(
+            violations(result),
+            vec![("created_at".to_string(), RuleKind::ReadOnly)]
+        );
+
+        let data = serde_json::json!({ "username": "alice", "password": "secret" });
+        let created = manager.create(&admin(), resource("1", data)).unwrap();
+        assert!(!created.data.contains_key("password"));
+        let created_at = created.data["created_at"].clone();
+
+        let fetched = manager.get(&admin(), "1").unwrap();
+        assert!(!fetched.data.contains_key("password"));
+        assert!(!manager.list(&admin()).unwrap()[0]
+            .data
+            .contains_key("password"));
+
+        // A client echoing the resource back keeps the stored password and creation time,
+        // while the update time is generated again
+        let mut stored = manager.storage.get("1").unwrap();
+        let long_ago = serde_json::json!("2020-01-01T00:00:00.000Z");
+        stored
+            .data
+            .insert("updated_at".to_string(), long_ago.clone());
+        manager.storage.update("1", stored).unwrap();
+        let mut changed = manager.get(&admin(), "1").unwrap();
+        changed
+            .data
+            .insert("bio".to_string(), serde_json::json!("Hello"));
+        let updated = manager.update(&admin(), "1", changed).unwrap();
+        assert_eq!(updated.data["created_at"], created_at);
+        assert_ne!(updated.data["updated_at"], long_ago);
+        assert_eq!(updated.data["bio"], "Hello");
+        assert_eq!(manager.storage.get("1").unwrap().data["password"], "secret");
+
+        let filters = vec![Filter::new("password", FilterOperator::Eq, "secret")];
+        match manager.list_filtered(&admin(), &filters) {
+            Err(MetaRestError::ValidationError(errors)) => {
+                assert_eq!(errors.violations[0].rule, RuleKind::WriteOnly)
+            }
+            _ => panic!("Expected ValidationError"),
+        }
+        let query = ListQuery::new().sort(SortKey::asc("password"));
+        assert!(manager.query(&admin(), &query).is_err());
+    }
+
+    #[test]
+    fn test_immutable_fields() {
+        let mut manager = create_account_manager();
+        let data = serde_json::json!({ "username": "alice", "password": "secret" });
+        manager.create(&admin(), resource("1", data)).unwrap();
+
+        let result = manager.update(
+            &admin(),
+            "1",
+            resource(
+                "1",
+                serde_json::json!({
+                    "username": "bob",
+                    "created_at": "2020-01-01T00:00:00Z"
+                }),
+            ),
+        );
+        assert_eq!(
+            violations(result),
+            vec![
+                ("username".to_string(), RuleKind::Immutable),
+                ("created_at".to_string(), RuleKind::ReadOnly)
+            ]
+        );
+
+        let updated = manager
+            .update(
+                &admin(),
+                "1",
+                resource("1", serde_json::json!({ "bio": "Hi" })),
+            )
+            .unwrap();
+        assert_eq!(updated.data["username"], "alice");
+    }
+
+    #[test]
+    fn test_conflicting_modifiers_rejected() {
+        let def: ResourceDefinition = serde_json::from_value(serde_json::json!({
+            "name": "orders",
+            "fields": [{
+                "name": "lines",
+                "field_type": "array",
+                "items": {
+                    "field_type": "object",
+                    "fields": [{ "name": "added_at", "field_type": "string", "read_only": true }]
+                }
+            }]
+        }))
+        .unwrap();
+        match ResourceManager::new(def, InMemoryStorage::new()) {
+            Err(MetaRestError::InvalidDefinition(msg)) => {
+                assert!(msg.contains("'lines[].added_at'"))
+            }
+            _ => panic!("Expected InvalidDefinition error"),
+        }
+
+        let mut def = create_test_definition();
+        def.fields[0].read_only = Some(true);
+        def.fields[0].write_only = Some(true);
+        assert!(matches!(
+            ResourceManager::new(def, InMemoryStorage::new()),
+            Err(MetaRestError::InvalidDefinition(_))
+        ));
+
+        let mut def = create_test_definition();
+        def.fields[0].read_only = Some(true);
+        match ResourceManager::new(def, InMemoryStorage::new()) {
+            Err(MetaRestError::InvalidDefinition(msg)) => assert!(msg.contains("no default")),
+            _ => panic!("Expected InvalidDefinition error"),
+        }
+    }
+
+    fn create_profile_manager(policy: &str) -> ResourceManager<InMemoryStorage> {
+        let def: ResourceDefinition = serde_json::from_value(serde_json::json!({
+            "name": "profiles",
+            "unknown_fields": policy,
+            "fields": [
+                { "name": "name", "field_type": "string", "required": true },
+                {
+                    "name": "address",
+                    "field_type": "object",
+                    "fields": [{ "name": "city", "field_type": "string" }]
+                },
+                {
+                    "name": "links",
+                    "field_type": "array",
+                    "items": {
+                        "field_type": "object",
+                        "fields": [{ "name": "url", "field_type": "string" }]
+                    }
+                }
+            ]
+        }))
+        .unwrap();
+        ResourceManager::new(def, InMemoryStorage::new()).unwrap()
+    }
+
+    fn profile() -> Resource {
+        resource(
+            "1",
+            serde_json::json!({
+                "name": "Alice",
+                "nickname": "Al",
+                "address": { "city": "Berlin", "country": "DE" },
+                "links": [{ "url": "https://example.com", "title": "Home" }]
+            }),
+        )
+    }
+
+    #[test]
+    fn test_unknown_fields_rejected() {
+        let mut manager = create_profile_manager("reject");
+        assert_eq!(
+            violations(manager.create(&admin(), profile())),
+            vec![
+                ("address.country".to_string(), RuleKind::Unknown),
+                ("links[0].title".to_string(), RuleKind::Unknown),
+                ("nickname".to_string(), RuleKind::Unknown)
+            ]
+        );
+
+        let data = serde_json::json!({ "name": "Alice", "address": { "city": "Berlin" } });
+        assert!(manager.create(&admin(), resource("1", data)).is_ok());
+    }
+
+    #[test]
+    fn test_unknown_fields_stripped_or_allowed() {
+        let mut manager = create_profile_manager("strip");
+        let created = manager.create(&admin(), profile()).unwrap();
+        assert_eq!(
+            serde_json::Value::from(created.data.into_iter().collect::<serde_json::Map<_, _>>()),
+            serde_json::json!({
+                "name": "Alice",
+                "address": { "city": "Berlin" },
+                "links": [{ "url": "https://example.com" }]
+            })
+        );
+        let updated = manager.update(&admin(), "1", profile()).unwrap();
+        assert!(!updated.data.contains_key("nickname"));
+
+        let mut manager = create_profile_manager("allow");
+        let created = manager.create(&admin(), profile()).unwrap();
+        assert_eq!(created.data["nickname"], "Al");
+        assert_eq!(created.data["address"]["country"], "DE");
+
+        let def = create_test_definition();
+        assert!(!serde_json::to_string(&def)
+            .unwrap()
+            .contains("unknown_fields"));
+    }
+
+    #[test]
+    fn test_nullable_fields() {
+        let def: ResourceDefinition = serde_json::from_value(serde_json::json!({
+            "name": "tasks",
+            "fields": [
+                { "name": "title", "field_type": "string", "required": true },
+                { "name": "due", "field_type": "string", "required": true, "nullable": true },
+                { "name": "note", "field_type": "string", "nullable": true },
+                {
+                    "name": "labels",
+                    "field_type": "array",
+                    "items": { "field_type": "string", "nullable": true }
+                }
+            ]
+        }))
+        .unwrap();
+        let mut manager = ResourceManager::new(def, InMemoryStorage::new()).unwrap();
+
+        // Null satisfies a required nullable field, and skips the other rules
+        let data = serde_json::json!({ "title": "A", "due": null, "labels": ["x", null] });
+        manager.create(&admin(), resource("1", data)).unwrap();
+
+        let data = serde_json::json!({ "title": null, "labels": [1] });
+        assert_eq!(
+            violations(manager.update(&admin(), "1", resource("1", data))),
+            vec![
+                ("title".to_string(), RuleKind::Null),
+                ("due".to_string(), RuleKind::Required),
+                ("labels[0]".to_string(), RuleKind::Type)
+            ]
+        );
+
+        let def = manager.definition();
+        assert!(serde_json::to_string(&def.fields[1])
+            .unwrap()
+            .contains("\"nullable\":true"));
+        assert!(!serde_json::to_string(&def.fields[0])
+            .unwrap()
+            .contains("nullable"));
+    }
+
+    #[test]
+    fn test_input_normalized_before_validation() {
+        let def: ResourceDefinition = serde_json::from_value(serde_json::json!({
+            "name": "contacts",
+            "fields": [
+                {
+                    "name": "name",
+                    "field_type": "string",
+                    "required": true,
+                    "transforms": ["collapse_whitespace"],
+                    "validation": { "min": 5.0 }
+                },
+                {
+                    "name": "email",
+                    "field_type": "string",
+                    "transforms": ["trim", "lowercase"],
+                    "allowed_values": ["alice@x.com"]
+                },
+                { "name": "age", "field_type": "integer", "coerce": true, "transforms": ["trim"] },
+                { "name": "vip", "field_type": "boolean", "coerce": true },
+                {
+                    "name": "tags",
+                    "field_type": "array",
+                    "items": { "field_type": "string", "transforms": ["uppercase"] }
+                }
+            ]
+        }))
+        .unwrap();
+        let mut manager = ResourceManager::new(def, InMemoryStorage::new()).unwrap();
+
+        let data = serde_json::json!({
+            "name": "  Alice   Smith ",
+            "email": " ALICE@X.COM",
+            "age": " 42 ",
+            "vip": "true",
+            "tags": ["a", "b"]
+        });
+        let created = manager.create(&admin(), resource("1", data)).unwrap();
+        assert_eq!(created.data["name"], "Alice Smith");
+        assert_eq!(created.data["email"], "alice@x.com");
+        assert_eq!(created.data["age"], 42);
+        assert_eq!(created.data["vip"], true);
+        assert_eq!(created.data["tags"], serde_json::json!(["A", "B"]));
+
+        let data = serde_json::json!({ "name": " Al ", "age": "forty" });
+        assert_eq!(
+            violations(manager.update(&admin(), "1", resource("1", data))),
+            vec![
+                ("name".to_string(), RuleKind::Min),
+                ("age".to_string(), RuleKind::Type)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_normalization_must_fit_field_type() {
+        for field in [
+            serde_json::json!({ "name": "tags", "field_type": "array", "coerce": true }),
+            serde_json::json!({ "name": "age", "field_type": "integer", "transforms": ["trim"] }),
+        ] {
+            let def = ResourceDefinition {
+                name: "things".to_string(),
+                fields: vec![serde_json::from_value(field).unwrap()],
+                ..Default::default()
+            };
+            assert!(matches!(
+                ResourceManager::new(def, InMemoryStorage::new()),
+                Err(MetaRestError::InvalidDefinition(_))
+            ));
+        }
+    }
+
+    fn create_contract_manager() -> ResourceManager<InMemoryStorage> {
+        let def: ResourceDefinition = serde_json::from_value(serde_json::json!({
+            "name": "contracts",
+            "fields": [
+                { "name": "kind", "field_type": "string", "required": true },
+                { "name": "vat_id", "field_type": "string", "nullable": true },
+                { "name": "start_date", "field_type": "string", "validation": { "format": "date" } },
+                { "name": "end_date", "field_type": "string", "validation": { "format": "date" } },
+                { "name": "email", "field_type": "string" },
+                { "name": "phone", "field_type": "string" }
+            ],
+            "rules": [
+                { "rule": "compare", "field": "end_date", "operator": "gt", "other": "start_date" },
+                {
+                    "rule": "required_if",
+                    "field": "vat_id",
+                    "when": { "field": "kind", "operator": "eq", "value": "company" }
+                },
+                { "rule": "mutually_exclusive", "fields": ["email", "phone"] }
+            ]
+        }))
+        .unwrap();
+        ResourceManager::new(def, InMemoryStorage::new()).unwrap()
+    }
+
+    #[test]
+    fn test_resource_rules() {
+        let mut manager = create_contract_manager();
+        let result = manager.create(
+            &admin(),
+            resource(
+                "1",
+                serde_json::json!({
+                    "kind": "company",
+                    "vat_id": null,
+                    "start_date": "2024-03-01",
+                    "end_date": "2024-02-01",
+                    "email": "a@x.com",
+                    "phone": "123"
+                }),
+            ),
+        );
+        match result {
+            Err(MetaRestError::ValidationError(errors)) => {
+                let rules: Vec<_> = errors
+                    .violations
+                    .iter()
+                    .map(|v| (&*v.field, v.rule))
+                    .collect();
+                assert_eq!(
+                    rules,
+                    vec![
+                        ("end_date", RuleKind::Compare),
+                        ("vat_id", RuleKind::Required),
+                        ("phone", RuleKind::Exclusive)
+                    ]
+                );
+                assert_eq!(
+                    errors.violations[0].message,
+                    "Field 'end_date' must be greater than field 'start_date'"
+                );
+                assert_eq!(errors.violations[0].expected, Some("2024-03-01".into()));
+            }
+            other => panic!("Expected ValidationError, got {:?}", other),
+        }
+
+        // Rules on missing fields only apply when their condition holds
+        let data = serde_json::json!({ "kind": "person", "end_date": "2024-02-01" });
+        assert!(manager.create(&admin(), resource("1", data)).is_ok());
+        let data = serde_json::json!({
+            "kind": "company",
+            "vat_id": "DE123",
+            "start_date": "2024-01-01",
+            "end_date": "2024-02-01",
+            "phone": "123"
+        });
+        let resource = resource("2", data);
+        assert!(manager.create(&admin(), resource).is_ok());
+    }
+
+    #[test]
+    fn test_invalid_resource_rules_rejected() {
+        for rule in [
+            serde_json::json!({ "rule": "compare", "field": "age", "operator": "contains", "other": "name" }),
+            serde_json::json!({ "rule": "compare", "field": "age", "operator": "lt", "other": "height" }),
+            serde_json::json!({
+                "rule": "required_if",
+                "field": "email",
+                "when": { "field": "name", "operator": "regex", "value": "[a-" }
+            }),
+            serde_json::json!({ "rule": "mutually_exclusive", "fields": ["email"] }),
+        ] {
+            let mut def = create_test_definition();
+            def.rules = vec![serde_json::from_value(rule).unwrap()];
+            assert!(matches!(
+                ResourceManager::new(def, InMemoryStorage::new()),
+                Err(MetaRestError::InvalidDefinition(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_custom_validators() {
+        let def: ResourceDefinition = serde_json::from_value(serde_json::json!({
+            "name": "payments",
+            "fields": [
+                { "name": "iban", "field_type": "string", "required": true, "validators": ["iban"] },
+                {
+                    "name": "skus",
+                    "field_type": "array",
+                    "items": { "field_type": "string", "validators": ["known_sku"] }
+                }
+            ]
+        }))
+        .unwrap();
+        assert!(matches!(
+            ResourceManager::new(def.clone(), InMemoryStorage::new()),
+            Err(MetaRestError::InvalidDefinition(msg)) if msg.contains("'iban'")
+        ));
+
+        let known = std::collections::HashSet::from(["A-1", "B-2"]);
+        let mut options = ManagerOptions::default();
+        options
+            .validators
+            .register("iban", |value| match value.as_str() {
+                Some(iban) if iban.starts_with("DE") && iban.len() == 22 => Ok(()),
+                _ => Err("not a German IBAN".to_string()),
+            });
+        options.validators.register("known_sku", move |value| {
+            match value.as_str().is_some_and(|sku| known.contains(sku)) {
+                true => Ok(()),
+                false => Err("unknown SKU".to_string()),
+            }
+        });
+        let mut manager =
+            ResourceManager::with_options(def, InMemoryStorage::new(), options).unwrap();
+
+        let data = serde_json::json!({ "iban": "DE89370400440532013000", "skus": ["A-1"] });
+        assert!(manager.create(&admin(), resource("1", data)).is_ok());
+
+        let data = serde_json::json!({ "iban": "FR76", "skus": ["A-1", "C-3", 4] });
+        let result = manager.update(&admin(), "1", resource("1", data));
+        assert_eq!(
+            violations(result),
+            vec![
+                ("iban".to_string(), RuleKind::Custom),
+                ("skus[1]".to_string(), RuleKind::Custom),
+                ("skus[2]".to_string(), RuleKind::Type)
+            ]
+        );
+    }
+
+    fn custom_types() -> TypeRegistry {
+        let money = FieldType::new(|value| {
+            value["amount"].is_number()
+                && value["currency"]
+                    .as_str()
+                    .is_some_and(|c| c.len() == 3 && c.chars().all(|c| c.is_ascii_uppercase()))
+        })
+        .normalize(|value| {
+            let parsed = value.as_str().and_then(|s| {
+                let (amount, currency) = s.split_once(' ')?;
+                Some(serde_json::json!({
+                    "amount": amount.parse::<f64>().ok()?,
+                    "currency": currency
+                }))
+            });
+            parsed.unwrap_or(value)
+        })
+        .compare(|a, b| {
+            let amount = |v: &serde_json::Value| v["amount"].as_f64().unwrap_or(f64::NAN);
+            a["currency"]
+                .as_str()
+                .cmp(&b["currency"].as_str())
+                .then_with(|| amount(a).total_cmp(&amount(b)))
+        });
+        let phone = FieldType::new(|value| {
+            value.as_str().is_some_and(|s| {
+                s.starts_with('+') && s.len() > 4 && s[1..].chars().all(|c| c.is_ascii_digit())
+            })
+        })
+        .normalize(|value| match value.as_str() {
+            Some(s) => s.replace([' ', '-', '(', ')'], "").into(),
+            None => value,
+        });
+        let geo_point = FieldType::new(|value| {
+            let lat = value["lat"].as_f64().unwrap_or(f64::NAN);
+            let lon = value["lon"].as_f64().unwrap_or(f64::NAN);
+            (-90.0..=90.0).contains(&lat) && (-180.0..=180.0).contains(&lon)
+        });
+
+        let mut types = TypeRegistry::new();
+        types.register("money", money).unwrap();
+        types.register("phone", phone).unwrap();
+        types.register("geo_point", geo_point).unwrap();
+        types
+    }
+
+    fn create_shop_manager() -> ResourceManager<InMemoryStorage> {
+        let def: ResourceDefinition = serde_json::from_value(serde_json::json!({
+            "name": "shops",
+            "fields": [
+                { "name": "price", "field_type": "money", "required": true },
+                { "name": "phone", "field_type": "phone", "transforms": ["trim"] },
+                { "name": "location", "field_type": "geo_point" }
+            ]
+        }))
+        .unwrap();
+        let options = ManagerOptions {
+            types: custom_types(),
+            ..Default::default()
+        };
+        ResourceManager::with_options(def, InMemoryStorage::new(), options).unwrap()
+    }
+
+    #[test]
+    fn test_unknown_field_type_rejected() {
+        let mut def = create_test_definition();
+        def.fields[0].field_type = "strng".to_string();
+        match ResourceManager::new(def, InMemoryStorage::new()) {
+            Err(MetaRestError::InvalidDefinition(msg)) => {
+                assert!(msg.contains("'name' has unknown type 'strng'"))
+            }
+            _ => panic!("Expected InvalidDefinition error"),
+        }
+    }
+
+    #[test]
+    fn test_custom_field_types() {
+        let mut manager = create_shop_manager();
+        let data = serde_json::json!({
+            "price": "12.50 EUR",
+            "phone": " +49 (30) 1234-5 ",
+            "location": { "lat": 52.5, "lon": 13.4 }
+        });
+        let created = manager.create(&admin(), resource("1", data)).unwrap();
+        assert_eq!(
+            created.data["price"],
+            serde_json::json!({ "amount": 12.5, "currency": "EUR" })
+        );
+        assert_eq!(created.data["phone"], "+493012345");
+
+        let data = serde_json::json!({
+            "price": "12.50 euro",
+            "phone": "030 12345",
+            "location": { "lat": 100.0, "lon": 13.4 }
+        });
+        let result = manager.update(&admin(), "1", resource("1", data));
+        assert_eq!(
+            violations(result),
+            vec![
+                ("price".to_string(), RuleKind::Type),
+                ("phone".to_string(), RuleKind::Type),
+                ("location".to_string(), RuleKind::Type)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_custom_comparison_in_queries() {
+        let mut manager = create_shop_manager();
+        for (id, price) in [
+            ("1", "9.50 EUR"),
+            ("2", "12 EUR"),
+            ("3", "100 USD"),
+            ("4", "10 EUR"),
+            ("5", "15 USD"),
+        ] {
+            let resource = resource(id, serde_json::json!({ "price": price }));
+            manager.create(&admin(), resource).unwrap();
+        }
+        let ids = |resources: &[Resource]| -> Vec<String> {
+            resources.iter().map(|r| r.id.clone()).collect()
+        };
+
+        // Amounts compare as numbers within a currency, and currencies rank by code
+        let at_least_ten = Filter::new(
+            "price",
+            FilterOperator::Gte,
+            serde_json::json!({ "amount": 10, "currency": "EUR" }),
+        );
+        let found = manager.list_filtered(&admin(), &[at_least_ten]).unwrap();
+        assert_eq!(ids(&found), vec!["2", "3", "4", "5"]);
+
+        let mixed = ListQuery::new().sort(SortKey::asc("price"));
+        let page = manager.query(&admin(), &mixed).unwrap();
+        assert_eq!(ids(&page.items), vec!["1", "4", "2", "5", "3"]);
+
+        let euros = Filter::new(
+            "price",
+            FilterOperator::Lt,
+            serde_json::json!({ "amount": 1000, "currency": "EUR" }),
+        );
+        let query = ListQuery::new()
+            .filter(euros.clone())
+            .sort(SortKey::asc("price"));
+        let page = manager.query(&admin(), &query).unwrap();
+        assert_eq!(ids(&page.items), vec!["1", "4", "2"]);
+
+        let cursor = CursorQuery::new(2)
+            .filter(euros)
+            .sort(SortKey::desc("price"));
+        let first = manager.query_cursor(&admin(), &cursor).unwrap();
+        let cursor = cursor.after(first.next_cursor.unwrap());
+        let second = manager.query_cursor(&admin(), &cursor).unwrap();
+        assert_eq!(ids(&first.items), vec!["2", "4"]);
+        assert_eq!(ids(&second.items), vec!["1"]);
+    }
+
+    #[test]
+    fn test_shared_types() {
+        let definition: ResourceDefinition = serde_json::from_value(serde_json::json!({
+            "name": "customers",
+            "shared_types": {
+                "address": {
+                    "field_type": "object",
+                    "fields": [
+                        { "name": "street", "field_type": "string", "required": true },
+                        { "name": "city", "field_type": "string", "required": true }
+                    ]
+                }
+            },
+            "fields": [
+                { "name": "billing", "$ref": "address", "required": true },
+                { "name": "shipping", "$ref": "address" }
+            ]
+        }))
+        .unwrap();
+        let mut manager = ResourceManager::new(definition, InMemoryStorage::new()).unwrap();
+        assert_eq!(
+            manager
+                .definition()
+                .field("shipping.city")
+                .unwrap()
+                .field_type,
+            "string"
+        );
+
+        let data = serde_json::json!({ "shipping": { "street": "Main St 1" } });
+        assert_eq!(
+            violations(manager.create(&admin(), resource("1", data))),
+            vec![
+                ("billing".to_string(), RuleKind::Required),
+                ("shipping.city".to_string(), RuleKind::Required)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_cyclic_shared_types_rejected() {
+        let definition: ResourceDefinition = serde_json::from_value(serde_json::json!({
+            "name": "categories",
+            "shared_types": {
+                "category": {
+                    "field_type": "object",
+                    "fields": [{ "name": "parent", "$ref": "category" }]
+                }
+            },
+            "fields": [{ "name": "category", "$ref": "category" }]
+        }))
+        .unwrap();
+        match ResourceManager::new(definition, InMemoryStorage::new()) {
+            Err(MetaRestError::InvalidDefinition(msg)) => {
+                assert!(msg.contains("category -> category"))
+            }
+            _ => panic!("Expected InvalidDefinition error"),
+        }
+    }
+
+    #[test]
+    fn test_definition_inheritance() {
+        let bases: Vec<ResourceDefinition> = serde_json::from_value(serde_json::json!([
+            {
+                "name": "timestamps",
+                "fields": [
+                    { "name": "created_at", "field_type": "string", "read_only": true,
+                      "immutable": true, "generate": "now" },
+                    { "name": "updated_at", "field_type": "string", "read_only": true,
+                      "generate": "now" }
+                ]
+            },
+            {
+                "name": "owned",
+                "fields": [{ "name": "owner", "field_type": "string", "required": true }],
+                "security": { "require_auth": true, "allowed_roles": ["admin"] }
+            }
+        ]))
+        .unwrap();
+        let mut definition = ResourceDefinition {
+            name: "notes".to_string(),
+            extends: Some("owned".to_string()),
+            mixins: vec!["timestamps".to_string()],
+            fields: vec![Field {
+                name: "text".to_string(),
+                field_type: "string".to_string(),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        assert!(matches!(
+            ResourceManager::new(definition.clone(), InMemoryStorage::new()),
+            Err(MetaRestError::InvalidDefinition(_))
+        ));
+
+        definition.resolve_inheritance(&bases).unwrap();
+        let mut manager = ResourceManager::new(definition, InMemoryStorage::new()).unwrap();
+        let created = manager.create(&admin(), resource("1", serde_json::json!({ "text": "Hi" })));
+        assert_eq!(
+            violations(created),
+            vec![("owner".to_string(), RuleKind::Required)]
+        );
+
+        let data = serde_json::json!({ "text": "Hi", "owner": "alice" });
+        let created = manager.create(&admin(), resource("1", data)).unwrap();
+        assert!(created.data.contains_key("created_at"));
+        let anonymous = manager.get(&CallerContext::anonymous(), "1");
+        assert!(matches!(anonymous, Err(MetaRestError::Unauthorized(_))));
+    }
 }
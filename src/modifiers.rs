@@ -0,0 +1,149 @@
+//! Read-only, write-only and immutable fields
+//!
+//! - Read-only fields cannot be set by clients; they get their values from defaults or
+//!   generators. On update, generated read-only fields such as `updated_at` are generated
+//!   again unless they are also immutable, and others keep their values.
+//! - Write-only fields are stored but never returned; an update without them keeps the
+//!   stored value, and they cannot be filtered or sorted on.
+//! - Immutable fields can be set on creation but not changed afterwards.
+//!
+//! Modifiers apply to top-level fields, and are rejected on nested fields and item schemas.
+
+use crate::filter::values_equal;
+use crate::{Field, MetaRestError, Resource, RuleKind, ValidationErrors, Violation};
+
+/// Check that modifiers are consistent and only used on top-level fields
+pub(crate) fn check_modifiers(fields: &[Field]) -> Result<(), MetaRestError> {
+    for field in fields {
+        if field.read_only == Some(true) && field.write_only == Some(true) {
+            return Err(MetaRestError::InvalidDefinition(format!(
+                "Field '{}' cannot be both read-only and write-only",
+                field.name
+            )));
+        }
+        if field.read_only == Some(true)
+            && field.required == Some(true)
+            && field.default.is_none()
+            && field.generate.is_none()
+        {
+            return Err(MetaRestError::InvalidDefinition(format!(
+                "Read-only field '{}' is required but has no default or generator",
+                field.name
+            )));
+        }
+        if let Some(path) = nested_modifier(field, &field.name) {
+            return Err(MetaRestError::InvalidDefinition(format!(
+                "Field '{}' has a modifier, which is only supported on top-level fields",
+                path
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Path of a nested field or item schema below `field` that has a modifier, if any
+fn nested_modifier(field: &Field, path: &str) -> Option<String> {
+    let nested = field
+        .fields
+        .iter()
+        .flatten()
+        .map(|f| (f, format!("{}.{}", path, f.name)));
+    let items = field.items.iter().map(|f| (&**f, format!("{}[]", path)));
+    nested
+        .chain(items)
+        .find_map(|(f, path)| match has_modifier(f) {
+            true => Some(path),
+            false => nested_modifier(f, &path),
+        })
+}
+
+fn has_modifier(field: &Field) -> bool {
+    field.read_only == Some(true) || field.write_only == Some(true) || field.immutable == Some(true)
+}
+
+/// Check client input against modifiers and carry over values the client cannot change
+///
+/// On creation `existing` is `None` and read-only fields must be absent. On update they may
+/// only repeat the stored value, as may immutable fields; read-only, immutable and
+/// write-only fields missing from the input keep their stored values, except generated
+/// read-only fields that are not immutable, which get a new value.
+pub(crate) fn apply_modifiers(
+    fields: &[Field],
+    resource: &mut Resource,
+    existing: Option<&Resource>,
+) -> Result<(), MetaRestError> {
+    let mut errors = ValidationErrors::default();
+
+    for field in fields.iter().filter(|field| has_modifier(field)) {
+        let stored = existing.and_then(|existing| existing.data.get(&field.name));
+        match resource.data.get(&field.name) {
+            Some(value) if stored.is_some_and(|stored| values_equal(stored, value)) => {}
+            Some(_) if field.read_only == Some(true) => errors.push(Violation::new(
+                &field.name,
+                RuleKind::ReadOnly,
+                format!("Field '{}' is read-only", field.name),
+            )),
+            Some(value) if field.immutable == Some(true) && existing.is_some() => errors.push(
+                Violation::new(
+                    &field.name,
+                    RuleKind::Immutable,
+                    format!("Field '{}' cannot be changed", field.name),
+                )
+                .with_values(stored.cloned().unwrap_or_default(), value.clone()),
+            ),
+            Some(_) => {}
+            None => {
+                if let Some(stored) = stored {
+                    resource.data.insert(field.name.clone(), stored.clone());
+                }
+            }
+        }
+        if existing.is_some() && field.read_only == Some(true) && field.immutable != Some(true) {
+            if let Some(generator) = field.generate {
+                resource
+                    .data
+                    .insert(field.name.clone(), generator.generate());
+            }
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(MetaRestError::ValidationError(errors))
+    }
+}
+
+/// Remove write-only fields from a resource returned to a client
+pub(crate) fn redact(fields: &[Field], mut resource: Resource) -> Resource {
+    for field in fields.iter().filter(|field| field.write_only == Some(true)) {
+        resource.data.remove(&field.name);
+    }
+    resource
+}
+
+/// Reject filtering or sorting on write-only fields, which would disclose their values
+pub(crate) fn check_readable<'a>(
+    fields: &[Field],
+    paths: impl IntoIterator<Item = &'a str>,
+) -> Result<(), MetaRestError> {
+    let mut errors = ValidationErrors::default();
+    for path in paths {
+        let name = path.split('.').next().unwrap_or_default();
+        if fields
+            .iter()
+            .any(|field| field.write_only == Some(true) && field.name == name)
+        {
+            errors.push(Violation::new(
+                path,
+                RuleKind::WriteOnly,
+                format!("Field '{}' is write-only and cannot be queried", path),
+            ));
+        }
+    }
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(MetaRestError::ValidationError(errors))
+    }
+}
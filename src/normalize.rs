@@ -0,0 +1,176 @@
+//! Normalization of client input
+//!
+//! Before a resource is validated, string values pass through the [`Transform`]s of their
+//! field, and fields marked with `coerce` convert strings such as `"42"` or `"true"` to
+//! their declared type. Values that cannot be coerced are left as they are, so validation
+//! reports them as type violations. Values of custom types are then normalized by their
+//! [`FieldType`](crate::FieldType).
+
+use crate::decimal::Decimal;
+use crate::{Field, MetaRestError, TypeRegistry};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Transformation of a string value
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Transform {
+    /// Remove leading and trailing whitespace
+    Trim,
+    /// Convert to lower case
+    Lowercase,
+    /// Convert to upper case
+    Uppercase,
+    /// Trim, and replace every run of whitespace with a single space
+    CollapseWhitespace,
+}
+
+impl Transform {
+    /// Apply the transformation to a string
+    pub fn apply(&self, value: &str) -> String {
+        match self {
+            Transform::Trim => value.trim().to_string(),
+            Transform::Lowercase => value.to_lowercase(),
+            Transform::Uppercase => value.to_uppercase(),
+            Transform::CollapseWhitespace => value.split_whitespace().collect::<Vec<_>>().join(" "),
+        }
+    }
+}
+
+/// Parse a string as a value of a scalar type, `None` if it is not one
+///
+/// Numbers must be represented exactly: a string with more digits than a JSON number keeps
+/// is not one, so that coercion never rounds a value.
+pub(crate) fn parse_scalar(field_type: &str, raw: &str) -> Option<serde_json::Value> {
+    match field_type {
+        "integer" => raw
+            .parse::<i64>()
+            .map(serde_json::Value::from)
+            .or_else(|_| raw.parse::<u64>().map(serde_json::Value::from))
+            .ok(),
+        "number" | "decimal" => parse_scalar("integer", raw).or_else(|| {
+            let number = serde_json::Number::from_f64(raw.parse().ok()?)?;
+            let exact = Decimal::parse(raw)?.compare(&Decimal::from_number(&number)?)?;
+            exact.is_eq().then_some(serde_json::Value::Number(number))
+        }),
+        "boolean" => match raw {
+            "true" => Some(true.into()),
+            "false" => Some(false.into()),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Check that transforms and coercion are only used where they apply
+pub(crate) fn check_normalization(fields: &[Field], prefix: &str) -> Result<(), MetaRestError> {
+    for field in fields {
+        let path = match prefix {
+            "" => field.name.clone(),
+            _ => format!("{}.{}", prefix, field.name),
+        };
+        check_field(field, &path)?;
+    }
+    Ok(())
+}
+
+fn check_field(field: &Field, path: &str) -> Result<(), MetaRestError> {
+    let coercible = matches!(
+        field.field_type.as_str(),
+        "integer" | "number" | "decimal" | "boolean"
+    );
+    if field.coerce == Some(true) && !coercible {
+        return Err(MetaRestError::InvalidDefinition(format!(
+            "Field '{}' of type '{}' cannot be coerced from strings",
+            path, field.field_type
+        )));
+    }
+    let structured = matches!(field.field_type.as_str(), "array" | "object");
+    if field.transforms.is_some() && (structured || coercible) && field.coerce != Some(true) {
+        return Err(MetaRestError::InvalidDefinition(format!(
+            "Field '{}' of type '{}' has transforms but does not take strings",
+            path, field.field_type
+        )));
+    }
+    if let Some(nested) = &field.fields {
+        check_normalization(nested, path)?;
+    }
+    if let Some(items) = &field.items {
+        check_field(items, &format!("{}[]", path))?;
+    }
+    Ok(())
+}
+
+/// Normalize the values of fields, including nested objects and array items
+pub(crate) fn normalize_fields(
+    fields: &[Field],
+    data: &mut HashMap<String, serde_json::Value>,
+    types: &TypeRegistry,
+) {
+    for field in fields {
+        if let Some(value) = data.get_mut(&field.name) {
+            normalize_value(field, value, types);
+        }
+    }
+}
+
+fn normalize_value(field: &Field, value: &mut serde_json::Value, types: &TypeRegistry) {
+    match value {
+        serde_json::Value::String(s) => {
+            for transform in field.transforms.iter().flatten() {
+                *s = transform.apply(s);
+            }
+            if field.coerce == Some(true) {
+                if let Some(coerced) = parse_scalar(&field.field_type, s) {
+                    *value = coerced;
+                }
+            }
+        }
+        serde_json::Value::Object(object) => {
+            for field in field.fields.iter().flatten() {
+                if let Some(value) = object.get_mut(&field.name) {
+                    normalize_value(field, value, types);
+                }
+            }
+        }
+        serde_json::Value::Array(array) => {
+            if let Some(items) = &field.items {
+                for item in array {
+                    normalize_value(items, item, types);
+                }
+            }
+        }
+        _ => {}
+    }
+    types.normalize(field, value);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_transforms() {
+        assert_eq!(Transform::Trim.apply("  Alice \n"), "Alice");
+        assert_eq!(Transform::Lowercase.apply("ALICE@X.COM"), "alice@x.com");
+        assert_eq!(Transform::Uppercase.apply("de"), "DE");
+        assert_eq!(
+            Transform::CollapseWhitespace.apply(" Alice \t  Smith "),
+            "Alice Smith"
+        );
+    }
+
+    #[test]
+    fn test_parse_scalar() {
+        assert_eq!(parse_scalar("integer", "42"), Some(json!(42)));
+        assert_eq!(parse_scalar("integer", "4.2"), None);
+        assert_eq!(parse_scalar("decimal", "19.99"), Some(json!(19.99)));
+        assert_eq!(parse_scalar("number", "1.5e3"), Some(json!(1500.0)));
+        assert_eq!(parse_scalar("decimal", "12345678901234567.89"), None);
+        assert_eq!(parse_scalar("number", "0.12345678901234567891"), None);
+        assert_eq!(parse_scalar("boolean", "false"), Some(json!(false)));
+        assert_eq!(parse_scalar("boolean", "yes"), None);
+        assert_eq!(parse_scalar("string", "42"), None);
+    }
+}
@@ -0,0 +1,544 @@
+//! List queries with sorting and pagination
+//!
+//! Two styles of paging are supported: [`ListQuery`] uses limit/offset windows, while
+//! [`CursorQuery`] uses keyset pagination with opaque continuation tokens, which stays
+//! consistent when resources are inserted or removed between pages.
+
+use crate::decimal::Decimal;
+use crate::types::Comparator;
+use crate::{FilterExpr, MetaRestError, Resource, RuleKind, Violation};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+/// Direction of a sort key
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SortDirection {
+    /// Smallest values first
+    #[default]
+    Asc,
+    /// Largest values first
+    Desc,
+}
+
+/// Placement of null or missing values in a sort
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NullsOrder {
+    /// Null values sort before all other values
+    First,
+    /// Null values sort after all other values
+    #[default]
+    Last,
+}
+
+/// A field to sort by
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SortKey {
+    /// Field name or dotted path of a nested field to sort on
+    pub field: String,
+    /// Sort direction
+    #[serde(default)]
+    pub direction: SortDirection,
+    /// Placement of null or missing values, independent of the direction
+    #[serde(default)]
+    pub nulls: NullsOrder,
+}
+
+impl SortKey {
+    /// Sort ascending by a field
+    pub fn asc(field: impl Into<String>) -> Self {
+        Self {
+            field: field.into(),
+            direction: SortDirection::Asc,
+            nulls: NullsOrder::default(),
+        }
+    }
+
+    /// Sort descending by a field
+    pub fn desc(field: impl Into<String>) -> Self {
+        Self {
+            direction: SortDirection::Desc,
+            ..Self::asc(field)
+        }
+    }
+
+    /// Set the placement of null or missing values
+    pub fn nulls(mut self, nulls: NullsOrder) -> Self {
+        self.nulls = nulls;
+        self
+    }
+
+    fn compare(&self, a: &Resource, b: &Resource, comparator: Option<&Comparator>) -> Ordering {
+        self.compare_values(self.value(a), self.value(b), comparator)
+    }
+
+    /// Value of the sort field, `None` when null or missing
+    fn value<'a>(&self, resource: &'a Resource) -> Option<&'a serde_json::Value> {
+        resource.value(&self.field).filter(|value| !value.is_null())
+    }
+
+    /// Compare sort field values, with the comparator of the field if any
+    fn compare_values(
+        &self,
+        a: Option<&serde_json::Value>,
+        b: Option<&serde_json::Value>,
+        comparator: Option<&Comparator>,
+    ) -> Ordering {
+        match (a, b) {
+            (None, None) => Ordering::Equal,
+            (None, Some(_)) => match self.nulls {
+                NullsOrder::First => Ordering::Less,
+                NullsOrder::Last => Ordering::Greater,
+            },
+            (Some(_), None) => match self.nulls {
+                NullsOrder::First => Ordering::Greater,
+                NullsOrder::Last => Ordering::Less,
+            },
+            (Some(a), Some(b)) => {
+                let ordering = match comparator {
+                    Some(compare) => compare(a, b),
+                    None => compare_values(a, b),
+                };
+                match self.direction {
+                    SortDirection::Asc => ordering,
+                    SortDirection::Desc => ordering.reverse(),
+                }
+            }
+        }
+    }
+}
+
+/// Query for listing resources: filters, sort keys and a page window
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ListQuery {
+    /// Filter expression resources must match
+    #[serde(default)]
+    pub filter: FilterExpr,
+    /// Sort keys, most significant first; ties are broken by resource id
+    #[serde(default)]
+    pub sort: Vec<SortKey>,
+    /// Maximum number of resources to return
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub limit: Option<usize>,
+    /// Number of matching resources to skip
+    #[serde(default)]
+    pub offset: usize,
+}
+
+impl ListQuery {
+    /// Create a query matching all resources
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a filter or filter expression that must also match
+    pub fn filter(mut self, filter: impl Into<FilterExpr>) -> Self {
+        self.filter = std::mem::take(&mut self.filter).and(filter);
+        self
+    }
+
+    /// Add a sort key
+    pub fn sort(mut self, key: SortKey) -> Self {
+        self.sort.push(key);
+        self
+    }
+
+    /// Limit the number of returned resources
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// Skip a number of matching resources
+    pub fn offset(mut self, offset: usize) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    /// Sort already filtered resources and cut out the requested page
+    ///
+    /// Storage backends that cannot sort or paginate natively can use this after filtering.
+    pub fn paginate(&self, resources: Vec<Resource>) -> Page {
+        self.paginate_with(resources, None)
+    }
+
+    /// Paginate, sorting fields of custom types and date formats with their comparators
+    pub(crate) fn paginate_with(
+        &self,
+        mut resources: Vec<Resource>,
+        comparators: Option<&HashMap<String, Comparator>>,
+    ) -> Page {
+        self.sort_resources_with(&mut resources, comparators);
+        let total = resources.len();
+        let items = resources
+            .into_iter()
+            .skip(self.offset)
+            .take(self.limit.unwrap_or(usize::MAX))
+            .collect();
+        Page { items, total }
+    }
+
+    /// Sort resources by the query's sort keys, then by id
+    pub fn sort_resources(&self, resources: &mut [Resource]) {
+        self.sort_resources_with(resources, None);
+    }
+
+    fn sort_resources_with(
+        &self,
+        resources: &mut [Resource],
+        comparators: Option<&HashMap<String, Comparator>>,
+    ) {
+        resources.sort_by(|a, b| {
+            self.sort
+                .iter()
+                .map(|key| key.compare(a, b, comparator(comparators, &key.field)))
+                .find(|ordering| ordering.is_ne())
+                .unwrap_or_else(|| a.id.cmp(&b.id))
+        });
+    }
+}
+
+/// One page of a list query
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Page {
+    /// Resources on this page
+    pub items: Vec<Resource>,
+    /// Number of resources matching the filters, across all pages
+    pub total: usize,
+}
+
+/// Query for keyset (cursor) pagination
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CursorQuery {
+    /// Filter expression resources must match
+    #[serde(default)]
+    pub filter: FilterExpr,
+    /// Sort keys, most significant first; ties are broken by resource id
+    #[serde(default)]
+    pub sort: Vec<SortKey>,
+    /// Maximum number of resources per page
+    pub limit: usize,
+    /// Continuation token from the previous page, `None` for the first page
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cursor: Option<String>,
+}
+
+impl CursorQuery {
+    /// Create a query for the first page of the given size
+    pub fn new(limit: usize) -> Self {
+        Self {
+            filter: FilterExpr::all(),
+            sort: Vec::new(),
+            limit,
+            cursor: None,
+        }
+    }
+
+    /// Add a filter or filter expression that must also match
+    pub fn filter(mut self, filter: impl Into<FilterExpr>) -> Self {
+        self.filter = std::mem::take(&mut self.filter).and(filter);
+        self
+    }
+
+    /// Add a sort key
+    pub fn sort(mut self, key: SortKey) -> Self {
+        self.sort.push(key);
+        self
+    }
+
+    /// Continue after the page that returned this token
+    pub fn after(mut self, cursor: impl Into<String>) -> Self {
+        self.cursor = Some(cursor.into());
+        self
+    }
+
+    /// Sort already filtered resources and cut out the page following the cursor
+    ///
+    /// Storage backends that cannot seek natively can use this after filtering. Fails with
+    /// a validation error if the cursor is malformed or was issued for a different sort.
+    pub fn paginate(&self, resources: Vec<Resource>) -> Result<CursorPage, MetaRestError> {
+        self.paginate_with(resources, None)
+    }
+
+    /// Paginate, sorting fields of custom types and date formats with their comparators
+    pub(crate) fn paginate_with(
+        &self,
+        mut resources: Vec<Resource>,
+        comparators: Option<&HashMap<String, Comparator>>,
+    ) -> Result<CursorPage, MetaRestError> {
+        if self.limit == 0 {
+            return Err(MetaRestError::ValidationError(
+                Violation::new("limit", RuleKind::Min, "Page limit must be at least 1")
+                    .with_values(1, 0)
+                    .into(),
+            ));
+        }
+        let after = self.cursor.as_deref().map(|c| self.decode(c)).transpose()?;
+
+        let ordering = ListQuery {
+            sort: self.sort.clone(),
+            ..ListQuery::default()
+        };
+        ordering.sort_resources_with(&mut resources, comparators);
+
+        let start = match &after {
+            Some(position) => {
+                resources.partition_point(|r| self.compare_to(r, position, comparators).is_le())
+            }
+            None => 0,
+        };
+        let mut items: Vec<Resource> = resources.into_iter().skip(start).collect();
+        let next_cursor = if items.len() > self.limit {
+            items.truncate(self.limit);
+            items.last().map(|last| self.encode(last))
+        } else {
+            None
+        };
+
+        Ok(CursorPage { items, next_cursor })
+    }
+
+    /// Compare a resource with a cursor position in sort order
+    fn compare_to(
+        &self,
+        resource: &Resource,
+        position: &CursorPosition,
+        comparators: Option<&HashMap<String, Comparator>>,
+    ) -> Ordering {
+        self.sort
+            .iter()
+            .zip(&position.values)
+            .map(|(key, value)| {
+                key.compare_values(
+                    key.value(resource),
+                    value.as_ref(),
+                    comparator(comparators, &key.field),
+                )
+            })
+            .find(|ordering| ordering.is_ne())
+            .unwrap_or_else(|| resource.id.as_str().cmp(&position.id))
+    }
+
+    fn encode(&self, last: &Resource) -> String {
+        let position = CursorPosition {
+            sort: self.sort.clone(),
+            values: self
+                .sort
+                .iter()
+                .map(|key| key.value(last).cloned())
+                .collect(),
+            id: last.id.clone(),
+        };
+        let json = serde_json::to_vec(&position).unwrap_or_default();
+        URL_SAFE_NO_PAD.encode(json)
+    }
+
+    fn decode(&self, cursor: &str) -> Result<CursorPosition, MetaRestError> {
+        let invalid = || {
+            MetaRestError::ValidationError(
+                Violation::new("cursor", RuleKind::Invalid, "Cursor is invalid or expired").into(),
+            )
+        };
+        let json = URL_SAFE_NO_PAD.decode(cursor).map_err(|_| invalid())?;
+        let position: CursorPosition = serde_json::from_slice(&json).map_err(|_| invalid())?;
+        if position.sort != self.sort || position.values.len() != self.sort.len() {
+            return Err(invalid());
+        }
+        Ok(position)
+    }
+}
+
+/// Decoded content of a continuation token
+///
+/// The sort keys are included so a cursor cannot be reused with a different ordering.
+#[derive(Debug, Serialize, Deserialize)]
+struct CursorPosition {
+    sort: Vec<SortKey>,
+    values: Vec<Option<serde_json::Value>>,
+    id: String,
+}
+
+/// One page of a cursor query
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CursorPage {
+    /// Resources on this page
+    pub items: Vec<Resource>,
+    /// Token for the next page, `None` on the last page
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
+}
+
+/// Comparator of the field at `path`, if it has a custom type or date format
+fn comparator<'a>(
+    comparators: Option<&'a HashMap<String, Comparator>>,
+    path: &str,
+) -> Option<&'a Comparator> {
+    comparators.and_then(|comparators| comparators.get(path))
+}
+
+/// Total order over JSON values used for sorting
+///
+/// Values of the same type compare naturally; values of different types are ordered
+/// by type: null, boolean, number, string, array, object. Numbers compare exactly as
+/// decimals where possible, and strings lexically.
+pub(crate) fn compare_values(a: &serde_json::Value, b: &serde_json::Value) -> Ordering {
+    use serde_json::Value;
+
+    fn rank(value: &Value) -> u8 {
+        match value {
+            Value::Null => 0,
+            Value::Bool(_) => 1,
+            Value::Number(_) => 2,
+            Value::String(_) => 3,
+            Value::Array(_) => 4,
+            Value::Object(_) => 5,
+        }
+    }
+
+    match (a, b) {
+        (Value::Bool(a), Value::Bool(b)) => a.cmp(b),
+        (Value::Number(a), Value::Number(b)) => Decimal::from_number(a)
+            .zip(Decimal::from_number(b))
+            .and_then(|(x, y)| x.compare(&y))
+            .unwrap_or_else(|| {
+                let (a, b) = (a.as_f64().unwrap_or(0.0), b.as_f64().unwrap_or(0.0));
+                a.total_cmp(&b)
+            }),
+        (Value::String(a), Value::String(b)) => a.cmp(b),
+        (Value::Array(_), Value::Array(_)) | (Value::Object(_), Value::Object(_)) => {
+            a.to_string().cmp(&b.to_string())
+        }
+        _ => rank(a).cmp(&rank(b)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::collections::HashMap;
+
+    fn resource(id: &str, data: serde_json::Value) -> Resource {
+        let data: HashMap<String, serde_json::Value> = serde_json::from_value(data).unwrap();
+        Resource {
+            id: id.to_string(),
+            data,
+        }
+    }
+
+    fn ids(page: &Page) -> Vec<&str> {
+        ids_of(&page.items)
+    }
+
+    fn ids_of(resources: &[Resource]) -> Vec<&str> {
+        resources.iter().map(|r| r.id.as_str()).collect()
+    }
+
+    fn sample() -> Vec<Resource> {
+        vec![
+            resource("1", json!({"team": "b", "age": 30})),
+            resource("2", json!({"team": "a", "age": 25})),
+            resource("3", json!({"team": "b", "age": null})),
+            resource("4", json!({"team": "a", "age": 41})),
+            resource("5", json!({"team": "b"})),
+        ]
+    }
+
+    #[test]
+    fn test_multi_field_sort() {
+        let query = ListQuery::new()
+            .sort(SortKey::asc("team"))
+            .sort(SortKey::desc("age"));
+        assert_eq!(
+            ids(&query.paginate(sample())),
+            vec!["4", "2", "1", "3", "5"]
+        );
+    }
+
+    #[test]
+    fn test_nulls_order() {
+        let query = ListQuery::new().sort(SortKey::desc("age").nulls(NullsOrder::First));
+        assert_eq!(
+            ids(&query.paginate(sample())),
+            vec!["3", "5", "4", "1", "2"]
+        );
+
+        let query = ListQuery::new().sort(SortKey::asc("age"));
+        assert_eq!(
+            ids(&query.paginate(sample())),
+            vec!["2", "1", "4", "3", "5"]
+        );
+    }
+
+    #[test]
+    fn test_limit_offset_and_total() {
+        let query = ListQuery::new()
+            .sort(SortKey::asc("age"))
+            .offset(1)
+            .limit(2);
+        let page = query.paginate(sample());
+        assert_eq!(page.total, 5);
+        assert_eq!(ids(&page), vec!["1", "4"]);
+
+        let page = ListQuery::new().offset(10).paginate(sample());
+        assert_eq!(page.total, 5);
+        assert!(page.items.is_empty());
+    }
+
+    #[test]
+    fn test_cursor_pages_stable_under_inserts() {
+        let query = CursorQuery::new(2).sort(SortKey::asc("age"));
+        let mut data = sample();
+
+        let page = query.paginate(data.clone()).unwrap();
+        assert_eq!(ids_of(&page.items), vec!["2", "1"]);
+
+        // A resource sorting before the cursor must not shift the next page
+        data.push(resource("6", json!({"age": 20})));
+        let query = query.after(page.next_cursor.unwrap());
+        let page = query.paginate(data.clone()).unwrap();
+        assert_eq!(ids_of(&page.items), vec!["4", "3"]);
+
+        let query = query.after(page.next_cursor.unwrap());
+        let page = query.paginate(data).unwrap();
+        assert_eq!(ids_of(&page.items), vec!["5"]);
+        assert!(page.next_cursor.is_none());
+    }
+
+    #[test]
+    fn test_cursor_rejected_for_different_sort() {
+        let page = CursorQuery::new(1)
+            .sort(SortKey::asc("age"))
+            .paginate(sample())
+            .unwrap();
+        let cursor = page.next_cursor.unwrap();
+
+        let query = CursorQuery::new(1).sort(SortKey::desc("age")).after(cursor);
+        assert!(matches!(
+            query.paginate(sample()),
+            Err(MetaRestError::ValidationError(_))
+        ));
+
+        let query = CursorQuery::new(1).after("not a cursor");
+        assert!(query.paginate(sample()).is_err());
+    }
+
+    #[test]
+    fn test_query_deserializes_from_json() {
+        let query: ListQuery = serde_json::from_value(json!({
+            "sort": [{ "field": "age", "direction": "desc", "nulls": "first" }],
+            "limit": 10
+        }))
+        .unwrap();
+        assert_eq!(
+            query.sort,
+            vec![SortKey::desc("age").nulls(NullsOrder::First)]
+        );
+        assert_eq!(query.limit, Some(10));
+        assert_eq!(query.offset, 0);
+    }
+}
@@ -0,0 +1,427 @@
+//! URL query-string parsing
+//!
+//! Turns HTTP query parameters into filters, sort keys and paging options:
+//!
+//! ```text
+//! ?age[gt]=30&name[contains]=Al&status[in]=new,open&sort=-age,name&limit=20&offset=40
+//! ```
+//!
+//! - `field=value` filters with `eq`, `field[op]=value` with any [`FilterOperator`]; nested
+//!   fields are addressed by dotted path, e.g. `address.city=Berlin`
+//! - `in`/`nin` take comma-separated values, `between` takes `low,high`; `isNull` and
+//!   `isNotNull` ignore their value, e.g. `deleted_at[isNull]`
+//! - `sort` lists fields, prefixed with `-` for descending order
+//! - `limit` and `offset` select an offset page, `cursor` a keyset page
+//!
+//! `sort`, `limit`, `offset` and `cursor` are reserved and cannot be used as field filters.
+//!
+//! Values are coerced to the field's declared type. Unknown fields, unknown operators,
+//! malformed keys such as `age[gt` and values that cannot be coerced are reported together
+//! as validation errors.
+
+use crate::normalize::parse_scalar;
+use crate::{
+    CursorQuery, Field, Filter, FilterExpr, FilterOperator, ListQuery, MetaRestError,
+    ResourceDefinition, RuleKind, SortKey, ValidationErrors, Violation,
+};
+
+/// Query options parsed from a URL query string
+#[derive(Debug, Clone, Default)]
+pub struct QueryParams {
+    /// Filters from field parameters, all of which must match
+    pub filter: FilterExpr,
+    /// Sort keys from the `sort` parameter
+    pub sort: Vec<SortKey>,
+    /// Page size from the `limit` parameter
+    pub limit: Option<usize>,
+    /// Offset from the `offset` parameter
+    pub offset: usize,
+    /// Continuation token from the `cursor` parameter; empty for the first page
+    pub cursor: Option<String>,
+}
+
+impl QueryParams {
+    /// Parse a query string (without the leading `?`) against a resource definition
+    pub fn parse(query: &str, definition: &ResourceDefinition) -> Result<Self, MetaRestError> {
+        let mut params = QueryParams::default();
+        let mut errors = ValidationErrors::default();
+
+        for (key, value) in query
+            .split('&')
+            .filter(|pair| !pair.is_empty())
+            .map(|pair| pair.split_once('=').unwrap_or((pair, "")))
+        {
+            let key = percent_decode(key, true);
+            let value = percent_decode(value, true);
+
+            match key.as_str() {
+                "sort" => {
+                    for name in value.split(',').filter(|name| !name.is_empty()) {
+                        let key = match name.strip_prefix('-') {
+                            Some(field) => SortKey::desc(field),
+                            None => SortKey::asc(name.strip_prefix('+').unwrap_or(name)),
+                        };
+                        if definition.field(&key.field).is_some() {
+                            params.sort.push(key);
+                        } else {
+                            errors.push(unknown_field(&key.field, definition));
+                        }
+                    }
+                }
+                "limit" => params.limit = parse_count("limit", &value, &mut errors),
+                "offset" => {
+                    params.offset = parse_count("offset", &value, &mut errors).unwrap_or_default()
+                }
+                "cursor" => params.cursor = Some(value),
+                _ => match parse_filter(&key, &value, definition) {
+                    Ok(filter) => params.filter = std::mem::take(&mut params.filter).and(filter),
+                    Err(violation) => errors.push(violation),
+                },
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(params)
+        } else {
+            Err(MetaRestError::ValidationError(errors))
+        }
+    }
+
+    /// Whether keyset paging was requested with a `cursor` parameter
+    pub fn is_cursor(&self) -> bool {
+        self.cursor.is_some()
+    }
+
+    /// Build an offset query
+    pub fn into_list_query(self) -> ListQuery {
+        ListQuery {
+            filter: self.filter,
+            sort: self.sort,
+            limit: self.limit,
+            offset: self.offset,
+        }
+    }
+
+    /// Build a keyset query, using `default_limit` when no `limit` was given
+    pub fn into_cursor_query(self, default_limit: usize) -> CursorQuery {
+        CursorQuery {
+            filter: self.filter,
+            sort: self.sort,
+            limit: self.limit.unwrap_or(default_limit),
+            cursor: self.cursor.filter(|cursor| !cursor.is_empty()),
+        }
+    }
+}
+
+/// Parse a `field` or `field[op]` parameter into a filter
+fn parse_filter(
+    key: &str,
+    value: &str,
+    definition: &ResourceDefinition,
+) -> Result<Filter, Violation> {
+    let malformed = |name: &str| {
+        let field = if name.is_empty() { key } else { name };
+        Violation::new(
+            field,
+            RuleKind::Invalid,
+            format!(
+                "Malformed filter parameter '{}', expected 'field' or 'field[operator]'",
+                key
+            ),
+        )
+        .with_values("field[operator]", key)
+    };
+    let (name, operator) = match key.split_once('[') {
+        Some((name, rest)) => {
+            let op = match rest.strip_suffix(']') {
+                Some(op) if !name.is_empty() && !op.contains(['[', ']']) => op,
+                _ => return Err(malformed(name)),
+            };
+            let operator = op.parse::<FilterOperator>().map_err(|_| {
+                Violation::new(
+                    name,
+                    RuleKind::Invalid,
+                    format!("Unknown filter operator '{}' on field '{}'", op, name),
+                )
+                .with_values(
+                    FilterOperator::ALL
+                        .iter()
+                        .map(|op| op.as_str())
+                        .collect::<Vec<_>>(),
+                    op,
+                )
+            })?;
+            (name, operator)
+        }
+        None if key.contains(']') => return Err(malformed(key)),
+        None => (key, FilterOperator::Eq),
+    };
+
+    let field = definition
+        .field(name)
+        .ok_or_else(|| unknown_field(name, definition))?;
+
+    let value = match operator {
+        FilterOperator::In | FilterOperator::Nin | FilterOperator::Between => value
+            .split(',')
+            .map(|item| coerce(name, field, item))
+            .collect::<Result<Vec<_>, _>>()?
+            .into(),
+        FilterOperator::StartsWith
+        | FilterOperator::EndsWith
+        | FilterOperator::IContains
+        | FilterOperator::Regex => value.into(),
+        FilterOperator::IsNull | FilterOperator::IsNotNull => serde_json::Value::Null,
+        FilterOperator::Exists => parse_bool(value)
+            .ok_or_else(|| type_mismatch(name, "boolean", value))?
+            .into(),
+        // Array elements are matched against the item type
+        FilterOperator::Contains => match &field.items {
+            Some(items) => coerce(name, items, value)?,
+            None => coerce(name, field, value)?,
+        },
+        _ => coerce(name, field, value)?,
+    };
+
+    Ok(Filter::new(name, operator, value))
+}
+
+/// Convert a raw parameter value to the declared type of the field at `path`
+fn coerce(path: &str, field: &Field, raw: &str) -> Result<serde_json::Value, Violation> {
+    match field.field_type.as_str() {
+        field_type @ ("integer" | "number" | "decimal" | "boolean") => {
+            let expected = match field_type {
+                "decimal" => "number",
+                other => other,
+            };
+            parse_scalar(field_type, raw).ok_or_else(|| type_mismatch(path, expected, raw))
+        }
+        "string" => Ok(raw.into()),
+        // Structured and custom types take JSON literals, falling back to plain strings
+        _ => Ok(serde_json::from_str(raw).unwrap_or_else(|_| raw.into())),
+    }
+}
+
+fn parse_bool(raw: &str) -> Option<bool> {
+    match raw {
+        "true" => Some(true),
+        "false" => Some(false),
+        _ => None,
+    }
+}
+
+fn parse_count(name: &str, raw: &str, errors: &mut ValidationErrors) -> Option<usize> {
+    match raw.parse() {
+        Ok(count) => Some(count),
+        Err(_) => {
+            errors.push(type_mismatch(name, "non-negative integer", raw));
+            None
+        }
+    }
+}
+
+fn type_mismatch(field: &str, expected: &str, raw: &str) -> Violation {
+    Violation::new(
+        field,
+        RuleKind::Type,
+        format!(
+            "Parameter '{}' value '{}' is not a valid {}",
+            field, raw, expected
+        ),
+    )
+    .with_values(expected, raw)
+}
+
+fn unknown_field(name: &str, definition: &ResourceDefinition) -> Violation {
+    Violation::new(
+        name,
+        RuleKind::Unknown,
+        format!("Unknown field '{}' of resource '{}'", name, definition.name),
+    )
+}
+
+/// Decode `%XX` escapes, and `+` as a space when decoding query strings
+pub(crate) fn percent_decode(input: &str, plus_as_space: bool) -> String {
+    let bytes = input.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'%' if i + 2 < bytes.len()
+                && bytes[i + 1].is_ascii_hexdigit()
+                && bytes[i + 2].is_ascii_hexdigit() =>
+            {
+                let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).unwrap_or_default();
+                decoded.push(u8::from_str_radix(hex, 16).unwrap_or_default());
+                i += 3;
+                continue;
+            }
+            b'+' if plus_as_space => decoded.push(b' '),
+            byte => decoded.push(byte),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SortDirection;
+    use serde_json::json;
+
+    fn definition() -> ResourceDefinition {
+        serde_json::from_value(json!({
+            "name": "users",
+            "fields": [
+                { "name": "name", "field_type": "string", "required": true },
+                { "name": "age", "field_type": "number", "required": false },
+                { "name": "active", "field_type": "boolean", "required": false },
+                { "name": "status", "field_type": "string", "required": false },
+                {
+                    "name": "scores",
+                    "field_type": "array",
+                    "required": false,
+                    "items": { "field_type": "number" }
+                },
+                {
+                    "name": "address",
+                    "field_type": "object",
+                    "required": false,
+                    "fields": [{ "name": "zip", "field_type": "number", "required": false }]
+                }
+            ]
+        }))
+        .unwrap()
+    }
+
+    fn violations(result: Result<QueryParams, MetaRestError>) -> Vec<(String, RuleKind)> {
+        match result {
+            Err(MetaRestError::ValidationError(errors)) => errors
+                .violations
+                .into_iter()
+                .map(|v| (v.field, v.rule))
+                .collect(),
+            other => panic!("Expected ValidationError, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_filters_sort_and_paging() {
+        let params = QueryParams::parse(
+            "age[gt]=30&name[contains]=Al&active=true&status[in]=new,open&sort=-age,name&limit=20&offset=40",
+            &definition(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            params.filter.leaves(),
+            vec![
+                &Filter::new("age", FilterOperator::Gt, 30),
+                &Filter::new("name", FilterOperator::Contains, "Al"),
+                &Filter::new("active", FilterOperator::Eq, true),
+                &Filter::new("status", FilterOperator::In, json!(["new", "open"])),
+            ]
+        );
+        assert_eq!(
+            params.sort,
+            vec![SortKey::desc("age"), SortKey::asc("name")]
+        );
+        assert_eq!(params.sort[0].direction, SortDirection::Desc);
+        assert!(!params.is_cursor());
+
+        let query = params.into_list_query();
+        assert_eq!(query.limit, Some(20));
+        assert_eq!(query.offset, 40);
+    }
+
+    #[test]
+    fn test_values_are_percent_decoded_and_coerced() {
+        let params =
+            QueryParams::parse("name=Alice+Smith%21&age[between]=18,30.5", &definition()).unwrap();
+        assert_eq!(
+            params.filter.leaves(),
+            vec![
+                &Filter::new("name", FilterOperator::Eq, "Alice Smith!"),
+                &Filter::new("age", FilterOperator::Between, json!([18, 30.5])),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_nested_field_paths() {
+        let params =
+            QueryParams::parse("address.zip[lt]=20000&sort=address.zip", &definition()).unwrap();
+        assert_eq!(
+            params.filter.leaves(),
+            vec![&Filter::new("address.zip", FilterOperator::Lt, 20000)]
+        );
+        assert_eq!(params.sort, vec![SortKey::asc("address.zip")]);
+
+        let result = QueryParams::parse("address.zip=x&address.city=y", &definition());
+        assert_eq!(
+            violations(result),
+            vec![
+                ("address.zip".to_string(), RuleKind::Type),
+                ("address.city".to_string(), RuleKind::Unknown),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_contains_uses_item_type() {
+        let params = QueryParams::parse("scores[contains]=5", &definition()).unwrap();
+        assert_eq!(
+            params.filter.leaves(),
+            vec![&Filter::new("scores", FilterOperator::Contains, 5)]
+        );
+    }
+
+    #[test]
+    fn test_null_operators_ignore_value() {
+        let params = QueryParams::parse("age[isNull]&status[isNotNull]=x", &definition()).unwrap();
+        assert_eq!(
+            params.filter.leaves(),
+            vec![
+                &Filter::new("age", FilterOperator::IsNull, serde_json::Value::Null),
+                &Filter::new("status", FilterOperator::IsNotNull, serde_json::Value::Null)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_cursor_params() {
+        let params = QueryParams::parse("cursor=&limit=5", &definition()).unwrap();
+        assert!(params.is_cursor());
+        let query = params.into_cursor_query(100);
+        assert_eq!(query.limit, 5);
+        assert_eq!(query.cursor, None);
+
+        let params = QueryParams::parse("cursor=abc", &definition()).unwrap();
+        let query = params.into_cursor_query(100);
+        assert_eq!(query.limit, 100);
+        assert_eq!(query.cursor.as_deref(), Some("abc"));
+    }
+
+    #[test]
+    fn test_all_errors_reported() {
+        let result = QueryParams::parse(
+            "color=red&age[like]=3&age=old&age[gt=3&age[gt]x=3&[eq]=1&active=maybe&sort=height&limit=-1",
+            &definition(),
+        );
+        assert_eq!(
+            violations(result),
+            vec![
+                ("color".to_string(), RuleKind::Unknown),
+                ("age".to_string(), RuleKind::Invalid),
+                ("age".to_string(), RuleKind::Type),
+                ("age".to_string(), RuleKind::Invalid),
+                ("age".to_string(), RuleKind::Invalid),
+                ("[eq]".to_string(), RuleKind::Invalid),
+                ("active".to_string(), RuleKind::Type),
+                ("height".to_string(), RuleKind::Unknown),
+                ("limit".to_string(), RuleKind::Type),
+            ]
+        );
+    }
+}
@@ -0,0 +1,260 @@
+//! Shared types and references in meta-descriptions
+//!
+//! A definition declares reusable field shapes in `shared_types`, and fields refer to them
+//! with `$ref`:
+//!
+//! ```json
+//! {
+//!   "name": "customers",
+//!   "shared_types": {
+//!     "address": { "field_type": "object", "fields": [
+//!       { "name": "city", "field_type": "string", "required": true }
+//!     ] }
+//!   },
+//!   "fields": [
+//!     { "name": "billing", "$ref": "address", "required": true },
+//!     { "name": "shipping", "$ref": "address" }
+//!   ]
+//! }
+//! ```
+//!
+//! Properties set on the referring field replace those of the shared type, one validation
+//! rule at a time. Flags such as `required` keep the value of the shared type unless they are
+//! set next to `$ref`, where `false` turns them off. Shared types can refer to other shared types, but not to
+//! themselves, directly or through others.
+
+use crate::{Field, MetaRestError, ValidationRule};
+use std::collections::{BTreeMap, HashMap};
+
+/// Resolver of references to a set of shared types
+struct Resolver<'a> {
+    shared: &'a BTreeMap<String, Field>,
+    resolved: HashMap<String, Field>,
+    /// Shared types being resolved, to detect cycles
+    stack: Vec<String>,
+}
+
+impl Resolver<'_> {
+    /// The field with its reference and those of its nested fields and items replaced
+    fn resolve_field(&mut self, field: &Field, path: &str) -> Result<Field, MetaRestError> {
+        let mut field = match &field.reference {
+            Some(name) => merge(self.resolve_shared(name, path)?, field),
+            None => field.clone(),
+        };
+        if let Some(nested) = field.fields.take() {
+            let nested = nested
+                .iter()
+                .map(|f| self.resolve_field(f, &format!("{}.{}", path, f.name)))
+                .collect::<Result<_, _>>()?;
+            field.fields = Some(nested);
+        }
+        if let Some(items) = field.items.take() {
+            let items = self.resolve_field(&items, &format!("{}[]", path))?;
+            field.items = Some(Box::new(items));
+        }
+        Ok(field)
+    }
+
+    fn resolve_shared(&mut self, name: &str, path: &str) -> Result<Field, MetaRestError> {
+        if let Some(field) = self.resolved.get(name) {
+            return Ok(field.clone());
+        }
+        if let Some(start) = self.stack.iter().position(|n| n == name) {
+            let mut cycle = self.stack[start..].to_vec();
+            cycle.push(name.to_string());
+            return Err(MetaRestError::InvalidDefinition(format!(
+                "Shared type '{}' refers to itself: {}",
+                name,
+                cycle.join(" -> ")
+            )));
+        }
+        let shared = self.shared.get(name).ok_or_else(|| {
+            MetaRestError::InvalidDefinition(format!(
+                "Field '{}' refers to unknown shared type '{}'",
+                path, name
+            ))
+        })?;
+
+        self.stack.push(name.to_string());
+        let field = self.resolve_field(shared, path)?;
+        self.stack.pop();
+        self.resolved.insert(name.to_string(), field.clone());
+        Ok(field)
+    }
+}
+
+/// A shared type with the properties set on the referring field applied on top
+fn merge(base: Field, field: &Field) -> Field {
+    Field {
+        name: field.name.clone(),
+        field_type: match field.field_type.as_str() {
+            "" => base.field_type,
+            _ => field.field_type.clone(),
+        },
+        reference: None,
+        required: field.required.or(base.required),
+        nullable: field.nullable.or(base.nullable),
+        validation: match (&field.validation, base.validation) {
+            (Some(rule), Some(base)) => Some(merge_validation(base, rule)),
+            (rule, base) => rule.clone().or(base),
+        },
+        fields: field.fields.clone().or(base.fields),
+        items: field.items.clone().or(base.items),
+        allowed_values: field.allowed_values.clone().or(base.allowed_values),
+        default: field.default.clone().or(base.default),
+        generate: field.generate.or(base.generate),
+        read_only: field.read_only.or(base.read_only),
+        write_only: field.write_only.or(base.write_only),
+        immutable: field.immutable.or(base.immutable),
+        validators: field.validators.clone().or(base.validators),
+        transforms: field.transforms.clone().or(base.transforms),
+        coerce: field.coerce.or(base.coerce),
+    }
+}
+
+/// Validation rules of a shared type with those set on the referring field applied on top
+fn merge_validation(base: ValidationRule, rule: &ValidationRule) -> ValidationRule {
+    ValidationRule {
+        min: rule.min.or(base.min),
+        max: rule.max.or(base.max),
+        pattern: rule.pattern.clone().or(base.pattern),
+        format: rule.format.or(base.format),
+        scale: rule.scale.or(base.scale),
+        precision: rule.precision.or(base.precision),
+        multiple_of: rule.multiple_of.or(base.multiple_of),
+        min_items: rule.min_items.or(base.min_items),
+        max_items: rule.max_items.or(base.max_items),
+        unique_items: rule.unique_items.or(base.unique_items),
+    }
+}
+
+/// Replace every reference in the fields with the shared type it names
+pub(crate) fn resolve_refs(
+    fields: &[Field],
+    shared: &BTreeMap<String, Field>,
+) -> Result<Vec<Field>, MetaRestError> {
+    let mut resolver = Resolver {
+        shared,
+        resolved: HashMap::new(),
+        stack: Vec::new(),
+    };
+    // Resolve every shared type, so that cycles are found even in unused ones
+    for name in shared.keys() {
+        resolver.resolve_shared(name, name)?;
+    }
+    fields
+        .iter()
+        .map(|field| resolver.resolve_field(field, &field.name))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn shared(types: serde_json::Value) -> BTreeMap<String, Field> {
+        serde_json::from_value(types).unwrap()
+    }
+
+    fn field(field: serde_json::Value) -> Field {
+        serde_json::from_value(field).unwrap()
+    }
+
+    #[test]
+    fn test_references_resolved() {
+        let shared = shared(json!({
+            "point": { "field_type": "number", "validation": { "min": 0.0 } },
+            "location": {
+                "field_type": "object",
+                "fields": [{ "name": "x", "$ref": "point", "required": true }]
+            }
+        }));
+        let fields = vec![
+            field(json!({ "name": "home", "$ref": "location", "required": true })),
+            field(json!({ "name": "path", "field_type": "array", "items": { "$ref": "point" } })),
+        ];
+
+        let resolved = resolve_refs(&fields, &shared).unwrap();
+        let home = &resolved[0];
+        assert_eq!(
+            (home.name.as_str(), home.field_type.as_str()),
+            ("home", "object")
+        );
+        assert!(home.required == Some(true) && home.reference.is_none());
+        let x = &home.fields.as_ref().unwrap()[0];
+        assert_eq!((x.field_type.as_str(), x.required), ("number", Some(true)));
+        assert_eq!(x.validation.as_ref().unwrap().min, Some(0.0));
+        assert_eq!(resolved[1].items.as_ref().unwrap().field_type, "number");
+    }
+
+    #[test]
+    fn test_flags_kept_unless_set_next_to_reference() {
+        let shared = shared(json!({
+            "address": { "field_type": "object", "required": true, "nullable": true, "fields": [] }
+        }));
+        let fields = vec![
+            field(json!({ "name": "billing", "$ref": "address" })),
+            field(
+                json!({ "name": "shipping", "$ref": "address", "required": false, "nullable": false }),
+            ),
+            field(json!({ "name": "pickup", "$ref": "address", "read_only": true })),
+        ];
+
+        let resolved = resolve_refs(&fields, &shared).unwrap();
+        let flags: Vec<_> = resolved
+            .iter()
+            .map(|f| (f.required, f.nullable, f.read_only))
+            .collect();
+        assert_eq!(
+            flags,
+            vec![
+                (Some(true), Some(true), None),
+                (Some(false), Some(false), None),
+                (Some(true), Some(true), Some(true))
+            ]
+        );
+        assert_eq!(
+            serde_json::to_value(&fields[0]).unwrap(),
+            json!({ "name": "billing", "$ref": "address" })
+        );
+    }
+
+    #[test]
+    fn test_validation_merged_rule_by_rule() {
+        let shared = shared(json!({
+            "money": {
+                "field_type": "string",
+                "validation": { "pattern": "^\\d+\\.\\d{2}$", "max": 12.0 }
+            }
+        }));
+        let fields = vec![field(
+            json!({ "name": "price", "$ref": "money", "validation": { "min": 4.0 } }),
+        )];
+
+        let resolved = resolve_refs(&fields, &shared).unwrap();
+        let rule = resolved[0].validation.as_ref().unwrap();
+        assert_eq!(rule.pattern.as_deref(), Some("^\\d+\\.\\d{2}$"));
+        assert_eq!((rule.min, rule.max), (Some(4.0), Some(12.0)));
+    }
+
+    #[test]
+    fn test_cycles_and_unknown_references_rejected() {
+        let cyclic = shared(json!({
+            "a": { "field_type": "object", "fields": [{ "name": "b", "$ref": "b" }] },
+            "b": { "field_type": "array", "items": { "$ref": "a" } }
+        }));
+        match resolve_refs(&[], &cyclic) {
+            Err(MetaRestError::InvalidDefinition(msg)) => assert!(msg.contains("a -> b -> a")),
+            _ => panic!("Expected InvalidDefinition error"),
+        }
+
+        let fields = vec![field(json!({ "name": "home", "$ref": "adress" }))];
+        match resolve_refs(&fields, &BTreeMap::new()) {
+            Err(MetaRestError::InvalidDefinition(msg)) => {
+                assert!(msg.contains("'home' refers to unknown shared type 'adress'"))
+            }
+            _ => panic!("Expected InvalidDefinition error"),
+        }
+    }
+}
@@ -0,0 +1,253 @@
+//! Resource-level validation rules
+//!
+//! Field rules check one value at a time. A [`ResourceRule`] relates several fields of a
+//! resource, such as an end date that must follow a start date:
+//!
+//! ```json
+//! [
+//!   { "rule": "compare", "field": "end_date", "operator": "gt", "other": "start_date" },
+//!   { "rule": "required_if", "field": "vat_id",
+//!     "when": { "field": "type", "operator": "eq", "value": "company" } },
+//!   { "rule": "mutually_exclusive", "fields": ["email", "phone"] }
+//! ]
+//! ```
+
+use crate::query::compare_values;
+use crate::types::Comparator;
+use crate::{
+    FilterExpr, FilterOperator, MetaRestError, Resource, ResourceDefinition, RuleKind,
+    ValidationErrors, Violation,
+};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+/// Rule relating several fields of a resource
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "rule", rename_all = "snake_case")]
+pub enum ResourceRule {
+    /// `field` compares to `other` with a comparison operator (`eq`, `ne`, `gt`, `gte`, `lt`
+    /// or `lte`); skipped unless both fields hold values of the same kind, or values the
+    /// comparator of the field can compare
+    Compare {
+        /// Path of the field on the left-hand side
+        field: String,
+        /// Comparison operator
+        operator: FilterOperator,
+        /// Path of the field on the right-hand side
+        other: String,
+    },
+    /// `field` must be present and not null when the condition matches
+    RequiredIf {
+        /// Path of the conditionally required field
+        field: String,
+        /// Condition on the resource
+        when: FilterExpr,
+    },
+    /// At most one of the fields may be present and not null
+    MutuallyExclusive {
+        /// Paths of the exclusive fields
+        fields: Vec<String>,
+    },
+}
+
+/// Wording of a comparison operator in messages, `None` for other operators
+fn comparison(operator: FilterOperator) -> Option<&'static str> {
+    match operator {
+        FilterOperator::Eq => Some("equal to"),
+        FilterOperator::Ne => Some("different from"),
+        FilterOperator::Gt => Some("greater than"),
+        FilterOperator::Gte => Some("at least"),
+        FilterOperator::Lt => Some("less than"),
+        FilterOperator::Lte => Some("at most"),
+        _ => None,
+    }
+}
+
+/// Whether a comparison operator holds for the ordering of its operands
+fn holds(operator: FilterOperator, ordering: Ordering) -> bool {
+    match operator {
+        FilterOperator::Eq => ordering.is_eq(),
+        FilterOperator::Ne => ordering.is_ne(),
+        FilterOperator::Gt => ordering.is_gt(),
+        FilterOperator::Gte => ordering.is_ge(),
+        FilterOperator::Lt => ordering.is_lt(),
+        FilterOperator::Lte => ordering.is_le(),
+        _ => true,
+    }
+}
+
+impl ResourceRule {
+    /// Paths of the fields the rule refers to, except those in a condition
+    fn paths(&self) -> Vec<&str> {
+        match self {
+            ResourceRule::Compare { field, other, .. } => vec![field, other],
+            ResourceRule::RequiredIf { field, .. } => vec![field],
+            ResourceRule::MutuallyExclusive { fields } => {
+                fields.iter().map(String::as_str).collect()
+            }
+        }
+    }
+
+    /// Check that the rule refers to declared fields and is well-formed, and compile the
+    /// regular expressions of its condition
+    fn compile(
+        &self,
+        definition: &ResourceDefinition,
+        comparators: &HashMap<String, Comparator>,
+    ) -> Result<CompiledRule, MetaRestError> {
+        let invalid = |message: String| Err(MetaRestError::InvalidDefinition(message));
+        let mut paths = self.paths();
+        let mut regexes = HashMap::new();
+        match self {
+            ResourceRule::Compare { operator, .. } if comparison(*operator).is_none() => {
+                return invalid(format!(
+                    "Rule comparing '{}' uses '{}', which is not a comparison operator",
+                    paths.join("' and '"),
+                    operator
+                ));
+            }
+            ResourceRule::RequiredIf { field, when } => {
+                match when.compile_regexes(Some(comparators)) {
+                    Ok(compiled) => regexes = compiled,
+                    Err(e) => {
+                        return invalid(format!(
+                            "Rule requiring '{}' has an invalid condition: {}",
+                            field, e
+                        ))
+                    }
+                }
+                paths.extend(when.leaves().iter().map(|filter| filter.field.as_str()));
+            }
+            ResourceRule::MutuallyExclusive { fields } if fields.len() < 2 => {
+                return invalid(format!(
+                    "Mutually exclusive rule needs at least two fields, got {:?}",
+                    fields
+                ));
+            }
+            _ => {}
+        }
+        match paths.iter().find(|path| definition.field(path).is_none()) {
+            Some(path) => invalid(format!("Rule refers to unknown field '{}'", path)),
+            None => Ok(CompiledRule {
+                rule: self.clone(),
+                regexes,
+            }),
+        }
+    }
+}
+
+/// A checked resource rule, with the regular expressions of its condition compiled once
+#[derive(Debug, Clone)]
+pub(crate) struct CompiledRule {
+    rule: ResourceRule,
+    regexes: HashMap<String, Regex>,
+}
+
+impl CompiledRule {
+    /// Record violations of the rule by a resource
+    fn evaluate(
+        &self,
+        resource: &Resource,
+        comparators: &HashMap<String, Comparator>,
+        errors: &mut ValidationErrors,
+    ) {
+        let present = |path: &str| resource.value(path).filter(|value| !value.is_null());
+        match &self.rule {
+            ResourceRule::Compare {
+                field,
+                operator,
+                other,
+            } => {
+                let (value, other_value) = match (present(field), present(other)) {
+                    (Some(value), Some(other_value)) => (value, other_value),
+                    _ => return,
+                };
+                let ordering = match comparators.get(field) {
+                    Some(compare) => Some(compare(value, other_value)),
+                    None if (value.is_number() && other_value.is_number())
+                        || (value.is_string() && other_value.is_string()) =>
+                    {
+                        Some(compare_values(value, other_value))
+                    }
+                    None => None,
+                };
+                if ordering.is_some_and(|ordering| !holds(*operator, ordering)) {
+                    errors.push(
+                        Violation::new(
+                            field,
+                            RuleKind::Compare,
+                            format!(
+                                "Field '{}' must be {} field '{}'",
+                                field,
+                                comparison(*operator).unwrap_or_default(),
+                                other
+                            ),
+                        )
+                        .with_values(other_value.clone(), value.clone()),
+                    );
+                }
+            }
+            ResourceRule::RequiredIf { field, when } => {
+                let matches = || {
+                    when.compiled(&self.regexes, Some(comparators))
+                        .matches(resource)
+                };
+                if present(field).is_none() && matches() {
+                    errors.push(Violation::new(
+                        field,
+                        RuleKind::Required,
+                        format!(
+                            "Field '{}' is required by the values of other fields",
+                            field
+                        ),
+                    ));
+                }
+            }
+            ResourceRule::MutuallyExclusive { fields } => {
+                let set: Vec<&str> = fields
+                    .iter()
+                    .map(String::as_str)
+                    .filter(|path| present(path).is_some())
+                    .collect();
+                for path in set.iter().skip(1) {
+                    errors.push(
+                        Violation::new(
+                            *path,
+                            RuleKind::Exclusive,
+                            format!("Field '{}' cannot be set together with '{}'", path, set[0]),
+                        )
+                        .with_values(fields.clone(), set.clone()),
+                    );
+                }
+            }
+        }
+    }
+}
+
+/// Check the resource rules of a definition and prepare them for evaluation
+pub(crate) fn check_rules(
+    definition: &ResourceDefinition,
+    comparators: &HashMap<String, Comparator>,
+) -> Result<Vec<CompiledRule>, MetaRestError> {
+    definition
+        .rules
+        .iter()
+        .map(|rule| rule.compile(definition, comparators))
+        .collect()
+}
+
+/// Evaluate the resource rules of a definition, recording every violation
+///
+/// Fields of custom types are compared with the comparators of their types.
+pub(crate) fn evaluate_rules(
+    rules: &[CompiledRule],
+    resource: &Resource,
+    comparators: &HashMap<String, Comparator>,
+    errors: &mut ValidationErrors,
+) {
+    for rule in rules {
+        rule.evaluate(resource, comparators, errors);
+    }
+}
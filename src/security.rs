@@ -0,0 +1,209 @@
+//! Security policies and caller identity
+//!
+//! Every [`ResourceManager`](crate::ResourceManager) operation is performed on behalf of a
+//! [`CallerContext`] and checked against the resource's [`SecurityPolicy`].
+
+use crate::MetaRestError;
+use serde::{Deserialize, Serialize};
+
+/// Security policy for a resource
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SecurityPolicy {
+    /// Whether authentication is required
+    pub require_auth: bool,
+    /// Allowed roles for access
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub allowed_roles: Option<Vec<String>>,
+    /// Overrides for read operations (GET)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub read: Option<AccessRule>,
+    /// Overrides for write operations (POST, PUT, DELETE)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub write: Option<AccessRule>,
+}
+
+/// Access requirements for one kind of operation
+///
+/// Unset values fall back to the values of the enclosing [`SecurityPolicy`].
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AccessRule {
+    /// Whether authentication is required
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub require_auth: Option<bool>,
+    /// Allowed roles for access; [`ANY_ROLE`] lifts the role restriction of the policy
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub allowed_roles: Option<Vec<String>>,
+}
+
+/// Role that, listed in the allowed roles of an [`AccessRule`], allows callers of any role
+pub const ANY_ROLE: &str = "*";
+
+/// Kind of operation being authorized
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Access {
+    /// GET of a single resource or a list
+    Read,
+    /// POST, PUT or DELETE
+    Write,
+}
+
+/// Identity of the caller performing an operation
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CallerContext {
+    /// Authenticated principal, `None` for anonymous callers
+    pub principal: Option<String>,
+    /// Roles granted to the principal
+    pub roles: Vec<String>,
+}
+
+impl CallerContext {
+    /// An unauthenticated caller
+    pub fn anonymous() -> Self {
+        Self::default()
+    }
+
+    /// An authenticated caller with the given roles
+    pub fn authenticated(principal: impl Into<String>, roles: &[&str]) -> Self {
+        Self {
+            principal: Some(principal.into()),
+            roles: roles.iter().map(|role| role.to_string()).collect(),
+        }
+    }
+
+    /// Whether the caller is authenticated
+    pub fn is_authenticated(&self) -> bool {
+        self.principal.is_some()
+    }
+
+    /// Whether the caller has the given role
+    pub fn has_role(&self, role: &str) -> bool {
+        self.roles.iter().any(|r| r == role)
+    }
+}
+
+impl SecurityPolicy {
+    /// Check whether a caller may perform an operation of the given kind
+    ///
+    /// Fails with [`MetaRestError::Unauthorized`] when authentication is required and the
+    /// caller is anonymous, and with [`MetaRestError::Forbidden`] when the caller has none
+    /// of the allowed roles. Role restrictions imply authentication, and a rule allowing
+    /// [`ANY_ROLE`] has none.
+    pub fn authorize(&self, access: Access, caller: &CallerContext) -> Result<(), MetaRestError> {
+        let rule = match access {
+            Access::Read => self.read.as_ref(),
+            Access::Write => self.write.as_ref(),
+        };
+        let require_auth = rule
+            .and_then(|rule| rule.require_auth)
+            .unwrap_or(self.require_auth);
+        let allowed_roles = rule
+            .and_then(|rule| rule.allowed_roles.as_ref())
+            .or(self.allowed_roles.as_ref())
+            .filter(|roles| !roles.iter().any(|role| role == ANY_ROLE));
+
+        if (require_auth || allowed_roles.is_some()) && !caller.is_authenticated() {
+            return Err(MetaRestError::Unauthorized(
+                "Authentication is required".to_string(),
+            ));
+        }
+
+        if let Some(roles) = allowed_roles {
+            if !roles.iter().any(|role| caller.has_role(role)) {
+                return Err(MetaRestError::Forbidden(format!(
+                    "Caller '{}' has none of the allowed roles: {}",
+                    caller.principal.as_deref().unwrap_or_default(),
+                    roles.join(", ")
+                )));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy() -> SecurityPolicy {
+        SecurityPolicy {
+            require_auth: false,
+            allowed_roles: None,
+            read: None,
+            write: Some(AccessRule {
+                require_auth: None,
+                allowed_roles: Some(vec!["editor".to_string()]),
+            }),
+        }
+    }
+
+    #[test]
+    fn test_open_policy_allows_anonymous() {
+        let policy = policy();
+        assert!(policy
+            .authorize(Access::Read, &CallerContext::anonymous())
+            .is_ok());
+    }
+
+    #[test]
+    fn test_role_restriction_requires_authentication() {
+        let policy = policy();
+        match policy.authorize(Access::Write, &CallerContext::anonymous()) {
+            Err(MetaRestError::Unauthorized(_)) => (),
+            _ => panic!("Expected Unauthorized error"),
+        }
+    }
+
+    #[test]
+    fn test_per_operation_roles() {
+        let policy = policy();
+        let viewer = CallerContext::authenticated("bob", &["viewer"]);
+        let editor = CallerContext::authenticated("alice", &["viewer", "editor"]);
+
+        assert!(policy.authorize(Access::Read, &viewer).is_ok());
+        match policy.authorize(Access::Write, &viewer) {
+            Err(MetaRestError::Forbidden(msg)) => assert!(msg.contains("editor")),
+            _ => panic!("Expected Forbidden error"),
+        }
+        assert!(policy.authorize(Access::Write, &editor).is_ok());
+    }
+
+    #[test]
+    fn test_rule_overrides_require_auth() {
+        let policy = SecurityPolicy {
+            require_auth: true,
+            allowed_roles: None,
+            read: Some(AccessRule {
+                require_auth: Some(false),
+                allowed_roles: None,
+            }),
+            write: None,
+        };
+        let anonymous = CallerContext::anonymous();
+        assert!(policy.authorize(Access::Read, &anonymous).is_ok());
+        assert!(policy.authorize(Access::Write, &anonymous).is_err());
+    }
+
+    #[test]
+    fn test_rule_lifts_role_restriction() {
+        let policy = SecurityPolicy {
+            require_auth: true,
+            allowed_roles: Some(vec!["admin".to_string()]),
+            read: Some(AccessRule {
+                require_auth: None,
+                allowed_roles: Some(vec![ANY_ROLE.to_string()]),
+            }),
+            write: None,
+        };
+        let user = CallerContext::authenticated("bob", &["viewer"]);
+        assert!(policy.authorize(Access::Read, &user).is_ok());
+        assert!(matches!(
+            policy.authorize(Access::Read, &CallerContext::anonymous()),
+            Err(MetaRestError::Unauthorized(_))
+        ));
+        assert!(matches!(
+            policy.authorize(Access::Write, &user),
+            Err(MetaRestError::Forbidden(_))
+        ));
+    }
+}
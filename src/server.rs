@@ -0,0 +1,675 @@
+//! HTTP server exposing a resource manager as REST routes
+//!
+//! Available with the `server` feature (enabled by default). A [`RestServer`] mounts every
+//! resource of a [`ServiceRegistry`] under `{base_path}/{name}` and serves the following
+//! routes for each of them:
+//!
+//! - `POST /{name}` - create a resource
+//! - `GET /{name}` - list resources, filtered, sorted and paged by the query string
+//! - `GET /{name}/{id}` - retrieve a specific resource
+//! - `PUT /{name}/{id}` - update a resource
+//! - `DELETE /{name}/{id}` - delete a resource
+//!
+//! Collection queries follow the syntax of [`QueryParams`]. Offset pages report the number
+//! of matching resources in an `X-Total-Count` header; keyset pages (requested with a
+//! `cursor` parameter, empty for the first page) return the next token in `X-Next-Cursor`.
+//!
+//! Requests are authorized against each resource's security policy. The caller identity is
+//! derived from request headers by an authenticator; without one every request is anonymous.
+
+use crate::query_string::percent_decode;
+use crate::{
+    CallerContext, MetaRestError, QueryParams, Resource, ResourceManager, RuleKind,
+    ServiceRegistry, ServiceSettings, Storage, Violation,
+};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+
+/// Page size of keyset queries that do not specify a `limit`
+pub const DEFAULT_PAGE_SIZE: usize = 50;
+
+/// Response produced by the router
+#[derive(Debug, Clone, PartialEq)]
+pub struct HttpResponse {
+    /// HTTP status code
+    pub status: u16,
+    /// JSON body, if any
+    pub body: Option<serde_json::Value>,
+    /// Additional response headers
+    pub headers: Vec<(String, String)>,
+}
+
+impl HttpResponse {
+    fn json(status: u16, body: serde_json::Value) -> Self {
+        Self {
+            status,
+            body: Some(body),
+            headers: Vec::new(),
+        }
+    }
+
+    fn empty(status: u16) -> Self {
+        Self {
+            status,
+            body: None,
+            headers: Vec::new(),
+        }
+    }
+
+    fn with_header(mut self, name: &str, value: impl Into<String>) -> Self {
+        self.headers.push((name.to_string(), value.into()));
+        self
+    }
+
+    fn error(status: u16, message: impl Into<String>) -> Self {
+        Self::json(status, serde_json::json!({ "error": message.into() }))
+    }
+}
+
+impl From<MetaRestError> for HttpResponse {
+    fn from(err: MetaRestError) -> Self {
+        let mut response = Self::error(status_code(&err), err.to_string());
+        if let (MetaRestError::ValidationError(errors), Some(body)) = (&err, &mut response.body) {
+            body["violations"] = to_json(&errors.violations);
+        }
+        response
+    }
+}
+
+/// Map an error to the HTTP status code reported to clients
+pub fn status_code(err: &MetaRestError) -> u16 {
+    match err {
+        MetaRestError::NotFound(_) => 404,
+        MetaRestError::ValidationError(_) => 400,
+        MetaRestError::InvalidOperation(_) => 409,
+        MetaRestError::Unauthorized(_) => 401,
+        MetaRestError::Forbidden(_) => 403,
+        MetaRestError::StorageError(_) | MetaRestError::InvalidDefinition(_) => 500,
+    }
+}
+
+/// Derives the caller identity from request headers (names are lowercase)
+pub type Authenticator = Box<dyn Fn(&HashMap<String, String>) -> CallerContext + Send + Sync>;
+
+/// Authenticator reading the principal from `X-Principal` and comma-separated roles
+/// from `X-Roles`
+///
+/// The headers are trusted as-is, so this is only suitable behind a gateway that
+/// authenticates clients and sets them.
+pub fn header_authenticator(headers: &HashMap<String, String>) -> CallerContext {
+    let principal = headers
+        .get("x-principal")
+        .map(|p| p.trim().to_string())
+        .filter(|p| !p.is_empty());
+    let roles = match principal {
+        Some(_) => headers
+            .get("x-roles")
+            .map(|roles| {
+                roles
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|role| !role.is_empty())
+                    .map(str::to_string)
+                    .collect()
+            })
+            .unwrap_or_default(),
+        None => Vec::new(),
+    };
+    CallerContext { principal, roles }
+}
+
+/// Request body accepted by POST and PUT
+///
+/// The `id` is required on POST; on PUT it is taken from the path and may be omitted.
+#[derive(Debug, Deserialize)]
+struct ResourceBody {
+    #[serde(default)]
+    id: Option<String>,
+    data: HashMap<String, serde_json::Value>,
+}
+
+/// REST server serving the resources of a service registry
+pub struct RestServer<S: Storage> {
+    registry: Mutex<ServiceRegistry<S>>,
+    authenticator: Authenticator,
+}
+
+impl<S: Storage + 'static> RestServer<S> {
+    /// Create a new server for a single resource manager
+    ///
+    /// Fails with [`MetaRestError::InvalidDefinition`] if the resource name is empty or
+    /// contains a `/`, as it cannot be mounted.
+    pub fn new(manager: ResourceManager<S>) -> Result<Self, MetaRestError> {
+        let mut registry = ServiceRegistry::new(ServiceSettings::default());
+        registry.register(manager)?;
+        Ok(Self::for_service(registry))
+    }
+
+    /// Create a new server for all resources of a service
+    pub fn for_service(registry: ServiceRegistry<S>) -> Self {
+        Self {
+            registry: Mutex::new(registry),
+            authenticator: Box::new(|_| CallerContext::anonymous()),
+        }
+    }
+
+    /// Use an authenticator to derive the caller of each request
+    pub fn with_authenticator<F>(mut self, authenticator: F) -> Self
+    where
+        F: Fn(&HashMap<String, String>) -> CallerContext + Send + Sync + 'static,
+    {
+        self.authenticator = Box::new(authenticator);
+        self
+    }
+
+    /// Route a single request and produce a response
+    ///
+    /// This is independent of the network layer, so it can be used to embed the
+    /// routes into another HTTP stack.
+    pub fn handle(
+        &self,
+        caller: &CallerContext,
+        method: &str,
+        url: &str,
+        body: &[u8],
+    ) -> HttpResponse {
+        let (path, query) = url.split_once('?').unwrap_or((url, ""));
+
+        let mut registry = match self.registry.lock() {
+            Ok(registry) => registry,
+            Err(_) => return HttpResponse::error(500, "Resource registry is unavailable"),
+        };
+
+        let base_path = registry.settings().base_path.clone().unwrap_or_default();
+        let relative = match strip_base_path(path, &base_path) {
+            Some(relative) => relative,
+            None => return HttpResponse::error(404, format!("No route for '{}'", path)),
+        };
+        let segments: Vec<String> = relative
+            .split('/')
+            .filter(|s| !s.is_empty())
+            .map(|segment| percent_decode(segment, false))
+            .collect();
+
+        let manager = match segments.first().and_then(|name| registry.get_mut(name)) {
+            Some(manager) => manager,
+            None => return HttpResponse::error(404, format!("No resource mounted at '{}'", path)),
+        };
+
+        let result = match (method, segments.get(1), segments.len()) {
+            ("POST", None, 1) => parse_body(body).and_then(|body| {
+                let id = body.id.ok_or_else(|| {
+                    MetaRestError::ValidationError(
+                        Violation::new("id", RuleKind::Required, "Field 'id' is missing").into(),
+                    )
+                })?;
+                let created = manager.create(
+                    caller,
+                    Resource {
+                        id,
+                        data: body.data,
+                    },
+                )?;
+                Ok(HttpResponse::json(201, to_json(&created)))
+            }),
+            ("GET", None, 1) => {
+                QueryParams::parse(query, manager.definition()).and_then(|params| {
+                    if params.is_cursor() {
+                        let page = manager
+                            .query_cursor(caller, &params.into_cursor_query(DEFAULT_PAGE_SIZE))?;
+                        let response = HttpResponse::json(200, to_json(&page.items));
+                        Ok(match page.next_cursor {
+                            Some(cursor) => response.with_header("X-Next-Cursor", cursor),
+                            None => response,
+                        })
+                    } else {
+                        let page = manager.query(caller, &params.into_list_query())?;
+                        Ok(HttpResponse::json(200, to_json(&page.items))
+                            .with_header("X-Total-Count", page.total.to_string()))
+                    }
+                })
+            }
+            ("GET", Some(id), 2) => manager
+                .get(caller, id)
+                .map(|resource| HttpResponse::json(200, to_json(&resource))),
+            ("PUT", Some(id), 2) => parse_body(body).and_then(|body| {
+                if body.id.as_deref().is_some_and(|body_id| body_id != id) {
+                    return Err(MetaRestError::ValidationError(
+                        Violation::new(
+                            "id",
+                            RuleKind::Invalid,
+                            format!("Body id does not match path id '{}'", id),
+                        )
+                        .into(),
+                    ));
+                }
+                let updated = manager.update(
+                    caller,
+                    id,
+                    Resource {
+                        id: id.clone(),
+                        data: body.data,
+                    },
+                )?;
+                Ok(HttpResponse::json(200, to_json(&updated)))
+            }),
+            ("DELETE", Some(id), 2) => manager.delete(caller, id).map(|_| HttpResponse::empty(204)),
+            (_, _, 1) | (_, _, 2) => {
+                return HttpResponse::error(405, format!("Method {} not allowed", method))
+            }
+            _ => return HttpResponse::error(404, format!("No route for '{}'", path)),
+        };
+
+        result.unwrap_or_else(HttpResponse::from)
+    }
+
+    /// Bind to an address and serve requests on a background thread
+    ///
+    /// Use port `0` to let the operating system pick a free port; the bound address
+    /// is available from [`ServerHandle::local_addr`].
+    pub fn start(self, addr: &str) -> Result<ServerHandle, MetaRestError> {
+        let http = tiny_http::Server::http(addr)
+            .map_err(|e| MetaRestError::InvalidOperation(format!("Cannot bind {}: {}", addr, e)))?;
+        let local_addr = http.server_addr().to_ip().ok_or_else(|| {
+            MetaRestError::InvalidOperation(format!("Address {} is not an IP address", addr))
+        })?;
+
+        let http = Arc::new(http);
+        let listener = Arc::clone(&http);
+        let thread = thread::spawn(move || {
+            for request in listener.incoming_requests() {
+                self.respond(request);
+            }
+        });
+
+        Ok(ServerHandle {
+            local_addr,
+            http,
+            thread: Some(thread),
+        })
+    }
+
+    fn respond(&self, mut request: tiny_http::Request) {
+        let headers: HashMap<String, String> = request
+            .headers()
+            .iter()
+            .map(|h| {
+                (
+                    h.field.as_str().as_str().to_lowercase(),
+                    h.value.to_string(),
+                )
+            })
+            .collect();
+        let caller = (self.authenticator)(&headers);
+
+        let mut body = Vec::new();
+        let response = match request.as_reader().read_to_end(&mut body) {
+            Ok(_) => self.handle(&caller, request.method().as_str(), request.url(), &body),
+            Err(e) => HttpResponse::error(400, format!("Cannot read request body: {}", e)),
+        };
+
+        let payload = response
+            .body
+            .map(|body| body.to_string())
+            .unwrap_or_default();
+        let mut reply = tiny_http::Response::from_string(payload).with_status_code(response.status);
+        if response.status != 204 {
+            let header = tiny_http::Header::from_bytes("Content-Type", "application/json")
+                .expect("static header is valid");
+            reply = reply.with_header(header);
+        }
+        for (name, value) in &response.headers {
+            if let Ok(header) = tiny_http::Header::from_bytes(name.as_bytes(), value.as_bytes()) {
+                reply = reply.with_header(header);
+            }
+        }
+        // The client may have gone away; there is nobody left to report the error to.
+        let _ = request.respond(reply);
+    }
+}
+
+/// Handle to a running server; stops the server when dropped
+pub struct ServerHandle {
+    local_addr: SocketAddr,
+    http: Arc<tiny_http::Server>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl ServerHandle {
+    /// Address the server is listening on
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    /// Stop accepting requests and wait for the server thread to finish
+    pub fn shutdown(mut self) {
+        self.stop();
+    }
+
+    fn stop(&mut self) {
+        self.http.unblock();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl Drop for ServerHandle {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/// Strip the service base path from a request path
+///
+/// Returns `None` when the path lies outside the base path.
+fn strip_base_path<'a>(path: &'a str, base_path: &str) -> Option<&'a str> {
+    let base_path = base_path.trim_end_matches('/');
+    let relative = path.strip_prefix(base_path)?;
+    if relative.is_empty() || relative.starts_with('/') {
+        Some(relative)
+    } else {
+        None
+    }
+}
+
+fn parse_body(body: &[u8]) -> Result<ResourceBody, MetaRestError> {
+    serde_json::from_slice(body).map_err(|e| {
+        MetaRestError::ValidationError(
+            Violation::new(
+                "",
+                RuleKind::Invalid,
+                format!("Invalid request body: {}", e),
+            )
+            .into(),
+        )
+    })
+}
+
+fn to_json<T: serde::Serialize>(value: &T) -> serde_json::Value {
+    serde_json::to_value(value).unwrap_or(serde_json::Value::Null)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{AccessRule, Field, InMemoryStorage, ResourceDefinition, SecurityPolicy};
+    use std::io::{Read, Write};
+    use std::net::TcpStream;
+
+    fn create_test_server() -> RestServer<InMemoryStorage> {
+        let definition = ResourceDefinition {
+            name: "users".to_string(),
+            fields: vec![Field {
+                name: "name".to_string(),
+                field_type: "string".to_string(),
+                required: Some(true),
+                ..Default::default()
+            }],
+            security: None,
+            ..Default::default()
+        };
+        RestServer::new(ResourceManager::new(definition, InMemoryStorage::new()).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_unmountable_resource_name_rejected() {
+        let definition = ResourceDefinition {
+            name: "users/admins".to_string(),
+            ..Default::default()
+        };
+        let manager = ResourceManager::new(definition, InMemoryStorage::new()).unwrap();
+        match RestServer::new(manager) {
+            Err(MetaRestError::InvalidDefinition(msg)) => assert!(msg.contains("users/admins")),
+            _ => panic!("Expected InvalidDefinition error"),
+        }
+    }
+
+    /// Send a raw HTTP/1.1 request and return the status code and body
+    fn send(addr: SocketAddr, method: &str, path: &str, body: &str) -> (u16, String) {
+        send_with_headers(addr, method, path, "", body)
+    }
+
+    fn send_with_headers(
+        addr: SocketAddr,
+        method: &str,
+        path: &str,
+        headers: &str,
+        body: &str,
+    ) -> (u16, String) {
+        let mut stream = TcpStream::connect(addr).unwrap();
+        write!(
+            stream,
+            "{} {} HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n{}Content-Length: {}\r\n\r\n{}",
+            method,
+            path,
+            headers,
+            body.len(),
+            body
+        )
+        .unwrap();
+
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        let (head, body) = response.split_once("\r\n\r\n").unwrap();
+        let status = head.split(' ').nth(1).unwrap().parse().unwrap();
+        (status, body.to_string())
+    }
+
+    #[test]
+    fn test_crud_over_loopback() {
+        let handle = create_test_server().start("127.0.0.1:0").unwrap();
+        let addr = handle.local_addr();
+
+        let (status, body) = send(
+            addr,
+            "POST",
+            "/users",
+            r#"{"id":"1","data":{"name":"Alice"}}"#,
+        );
+        assert_eq!(status, 201);
+        assert!(body.contains("Alice"));
+
+        let (status, body) = send(addr, "GET", "/users/1", "");
+        assert_eq!(status, 200);
+        let resource: Resource = serde_json::from_str(&body).unwrap();
+        assert_eq!(resource.data["name"], "Alice");
+
+        let (status, _) = send(addr, "PUT", "/users/1", r#"{"data":{"name":"Alicia"}}"#);
+        assert_eq!(status, 200);
+
+        let (status, body) = send(addr, "GET", "/users", "");
+        assert_eq!(status, 200);
+        let resources: Vec<Resource> = serde_json::from_str(&body).unwrap();
+        assert_eq!(resources.len(), 1);
+        assert_eq!(resources[0].data["name"], "Alicia");
+
+        let (status, _) = send(addr, "DELETE", "/users/1", "");
+        assert_eq!(status, 204);
+
+        let (status, _) = send(addr, "GET", "/users/1", "");
+        assert_eq!(status, 404);
+
+        handle.shutdown();
+    }
+
+    #[test]
+    fn test_error_status_codes() {
+        let server = create_test_server();
+        let anon = CallerContext::anonymous();
+
+        let response = server.handle(&anon, "POST", "/users", br#"{"id":"1","data":{}}"#);
+        assert_eq!(response.status, 400);
+        let violations = &response.body.unwrap()["violations"];
+        assert_eq!(violations[0]["field"], "name");
+        assert_eq!(violations[0]["rule"], "required");
+
+        let response = server.handle(&anon, "POST", "/users", b"not json");
+        assert_eq!(response.status, 400);
+
+        let body = br#"{"id":"1","data":{"name":"Alice"}}"#;
+        assert_eq!(server.handle(&anon, "POST", "/users", body).status, 201);
+        assert_eq!(server.handle(&anon, "POST", "/users", body).status, 409);
+
+        assert_eq!(server.handle(&anon, "DELETE", "/users/2", b"").status, 404);
+        assert_eq!(server.handle(&anon, "GET", "/orders", b"").status, 404);
+        assert_eq!(server.handle(&anon, "PATCH", "/users/1", b"").status, 405);
+    }
+
+    #[test]
+    fn test_service_resources_mounted_under_base_path() {
+        let registry = ServiceRegistry::from_json(
+            r#"{
+                "name": "shop",
+                "settings": { "base_path": "/api/v1/" },
+                "resources": [
+                    { "name": "products", "fields": [] },
+                    { "name": "orders", "fields": [] }
+                ]
+            }"#,
+            |_| InMemoryStorage::new(),
+        )
+        .unwrap();
+        let server = RestServer::for_service(registry);
+        let anon = CallerContext::anonymous();
+
+        let body = br#"{"id":"1","data":{}}"#;
+        assert_eq!(
+            server
+                .handle(&anon, "POST", "/api/v1/products", body)
+                .status,
+            201
+        );
+        assert_eq!(
+            server
+                .handle(&anon, "GET", "/api/v1/products/1", b"")
+                .status,
+            200
+        );
+        assert_eq!(
+            server.handle(&anon, "GET", "/api/v1/orders/1", b"").status,
+            404
+        );
+        assert_eq!(server.handle(&anon, "GET", "/products/1", b"").status, 404);
+        assert_eq!(
+            server.handle(&anon, "GET", "/api/v1x/products", b"").status,
+            404
+        );
+    }
+
+    #[test]
+    fn test_security_policy_over_loopback() {
+        let definition = ResourceDefinition {
+            name: "notes".to_string(),
+            fields: vec![],
+            security: Some(SecurityPolicy {
+                require_auth: true,
+                allowed_roles: None,
+                read: None,
+                write: Some(AccessRule {
+                    require_auth: None,
+                    allowed_roles: Some(vec!["editor".to_string()]),
+                }),
+            }),
+            ..Default::default()
+        };
+        let manager = ResourceManager::new(definition, InMemoryStorage::new()).unwrap();
+        let handle = RestServer::new(manager)
+            .unwrap()
+            .with_authenticator(header_authenticator)
+            .start("127.0.0.1:0")
+            .unwrap();
+        let addr = handle.local_addr();
+        let body = r#"{"id":"1","data":{}}"#;
+
+        let (status, _) = send(addr, "POST", "/notes", body);
+        assert_eq!(status, 401);
+
+        let viewer = "X-Principal: bob\r\nX-Roles: viewer\r\n";
+        let (status, _) = send_with_headers(addr, "POST", "/notes", viewer, body);
+        assert_eq!(status, 403);
+
+        let editor = "X-Principal: alice\r\nX-Roles: viewer, editor\r\n";
+        let (status, _) = send_with_headers(addr, "POST", "/notes", editor, body);
+        assert_eq!(status, 201);
+
+        let (status, _) = send_with_headers(addr, "GET", "/notes/1", viewer, "");
+        assert_eq!(status, 200);
+    }
+
+    #[test]
+    fn test_path_segments_are_percent_decoded() {
+        let server = create_test_server();
+        let anon = CallerContext::anonymous();
+        let body = br#"{"id":"a b","data":{"name":"Alice"}}"#;
+        assert_eq!(server.handle(&anon, "POST", "/users", body).status, 201);
+        assert_eq!(server.handle(&anon, "GET", "/users/a%20b", b"").status, 200);
+    }
+
+    #[test]
+    fn test_collection_query_string() {
+        let registry = ServiceRegistry::from_json(
+            r#"{
+                "name": "people",
+                "resources": [{
+                    "name": "users",
+                    "fields": [
+                        { "name": "name", "field_type": "string", "required": true },
+                        { "name": "age", "field_type": "number", "required": false }
+                    ]
+                }]
+            }"#,
+            |_| InMemoryStorage::new(),
+        )
+        .unwrap();
+        let server = RestServer::for_service(registry);
+        let anon = CallerContext::anonymous();
+        for (id, name, age) in [("1", "Alice", 30), ("2", "Bob", 25), ("3", "Carol", 35)] {
+            let body = serde_json::json!({ "id": id, "data": { "name": name, "age": age } });
+            let response = server.handle(&anon, "POST", "/users", body.to_string().as_bytes());
+            assert_eq!(response.status, 201);
+        }
+
+        let ids = |response: &HttpResponse| -> Vec<String> {
+            serde_json::from_value::<Vec<Resource>>(response.body.clone().unwrap())
+                .unwrap()
+                .into_iter()
+                .map(|r| r.id)
+                .collect()
+        };
+
+        let response = server.handle(&anon, "GET", "/users?age[gte]=30&sort=-age", b"");
+        assert_eq!(response.status, 200);
+        assert_eq!(ids(&response), vec!["3", "1"]);
+        assert_eq!(
+            response.headers,
+            vec![("X-Total-Count".to_string(), "2".to_string())]
+        );
+
+        let response = server.handle(&anon, "GET", "/users?sort=age&limit=1&offset=1", b"");
+        assert_eq!(ids(&response), vec!["1"]);
+        assert_eq!(response.headers[0].1, "3");
+
+        let response = server.handle(&anon, "GET", "/users?cursor=&sort=name&limit=2", b"");
+        assert_eq!(ids(&response), vec!["1", "2"]);
+        let (name, cursor) = &response.headers[0];
+        assert_eq!(name, "X-Next-Cursor");
+        let url = format!("/users?cursor={}&sort=name&limit=2", cursor);
+        let response = server.handle(&anon, "GET", &url, b"");
+        assert_eq!(ids(&response), vec!["3"]);
+        assert!(response.headers.is_empty());
+
+        let response = server.handle(&anon, "GET", "/users?color=red&age=old", b"");
+        assert_eq!(response.status, 400);
+        assert_eq!(
+            response.body.unwrap()["violations"]
+                .as_array()
+                .unwrap()
+                .len(),
+            2
+        );
+    }
+}
@@ -0,0 +1,378 @@
+//! Multi-resource service definitions
+//!
+//! A service is a bunch of resources described by a single meta-description. The
+//! [`ServiceRegistry`] builds one [`ResourceManager`] per resource and looks them up by name.
+
+use crate::{
+    Field, InMemoryStorage, ManagerOptions, MetaRestError, ResourceDefinition, ResourceManager,
+    SecurityPolicy, Storage,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+/// Service meta-description holding many resource definitions
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServiceDefinition {
+    /// Name of the service
+    pub name: String,
+    /// Version of the service
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    /// Service-wide settings
+    #[serde(default)]
+    pub settings: ServiceSettings,
+    /// Resources that make up the service
+    pub resources: Vec<ResourceDefinition>,
+    /// Definitions that resources extend or include as mixins, without being resources
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub bases: Vec<ResourceDefinition>,
+    /// Field shapes available to the fields of every resource, by name
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub shared_types: BTreeMap<String, Field>,
+}
+
+/// Settings shared by all resources of a service
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ServiceSettings {
+    /// Path prefix under which resources are mounted (e.g. "/api/v1")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub base_path: Option<String>,
+    /// Security policy for resources that do not define their own
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_security: Option<SecurityPolicy>,
+}
+
+impl ServiceDefinition {
+    /// Parse a service definition from a JSON meta-description
+    pub fn from_json(json: &str) -> Result<Self, MetaRestError> {
+        serde_json::from_str(json).map_err(|e| {
+            MetaRestError::InvalidDefinition(format!("Invalid service definition: {}", e))
+        })
+    }
+}
+
+/// Registry holding one resource manager per resource name
+pub struct ServiceRegistry<S: Storage> {
+    settings: ServiceSettings,
+    managers: HashMap<String, ResourceManager<S>>,
+    order: Vec<String>,
+}
+
+impl<S: Storage> ServiceRegistry<S> {
+    /// Create an empty registry with the given settings
+    pub fn new(settings: ServiceSettings) -> Self {
+        Self {
+            settings,
+            managers: HashMap::new(),
+            order: Vec::new(),
+        }
+    }
+
+    /// Build a registry from a service definition
+    ///
+    /// `storage` is called once per resource to create its storage backend. Resources
+    /// first inherit from the service's `bases`; those still without a security policy
+    /// inherit the service's default policy. Resources see the service's shared types next
+    /// to their own, which take precedence.
+    pub fn from_definition<F>(
+        definition: ServiceDefinition,
+        storage: F,
+    ) -> Result<Self, MetaRestError>
+    where
+        F: FnMut(&ResourceDefinition) -> S,
+    {
+        Self::from_definition_with(definition, &ManagerOptions::default(), storage)
+    }
+
+    /// Build a registry from a service definition whose fields can use the custom
+    /// validators and field types of the options
+    ///
+    /// Fails if a field of any resource refers to a validator or type that is not
+    /// registered.
+    pub fn from_definition_with<F>(
+        definition: ServiceDefinition,
+        options: &ManagerOptions,
+        mut storage: F,
+    ) -> Result<Self, MetaRestError>
+    where
+        F: FnMut(&ResourceDefinition) -> S,
+    {
+        let mut registry = Self::new(definition.settings);
+        for mut resource in definition.resources {
+            resource.resolve_inheritance(&definition.bases)?;
+            if resource.security.is_none() {
+                resource.security = registry.settings.default_security.clone();
+            }
+            for (name, shared) in &definition.shared_types {
+                resource
+                    .shared_types
+                    .entry(name.clone())
+                    .or_insert_with(|| shared.clone());
+            }
+            let backend = storage(&resource);
+            registry.register(ResourceManager::with_options(
+                resource,
+                backend,
+                options.clone(),
+            )?)?;
+        }
+        Ok(registry)
+    }
+
+    /// Build a registry from a JSON service meta-description
+    pub fn from_json<F>(json: &str, storage: F) -> Result<Self, MetaRestError>
+    where
+        F: FnMut(&ResourceDefinition) -> S,
+    {
+        Self::from_definition(ServiceDefinition::from_json(json)?, storage)
+    }
+
+    /// Add a resource manager to the registry
+    pub fn register(&mut self, manager: ResourceManager<S>) -> Result<(), MetaRestError> {
+        let name = manager.definition().name.clone();
+        if name.is_empty() || name.contains('/') {
+            return Err(MetaRestError::InvalidDefinition(format!(
+                "Invalid resource name '{}'",
+                name
+            )));
+        }
+        if self.managers.contains_key(&name) {
+            return Err(MetaRestError::InvalidDefinition(format!(
+                "Resource '{}' is defined more than once",
+                name
+            )));
+        }
+        self.order.push(name.clone());
+        self.managers.insert(name, manager);
+        Ok(())
+    }
+
+    /// Get the manager for a resource
+    pub fn get(&self, name: &str) -> Option<&ResourceManager<S>> {
+        self.managers.get(name)
+    }
+
+    /// Get the manager for a resource mutably
+    pub fn get_mut(&mut self, name: &str) -> Option<&mut ResourceManager<S>> {
+        self.managers.get_mut(name)
+    }
+
+    /// Names of registered resources, in definition order
+    pub fn resource_names(&self) -> impl Iterator<Item = &str> {
+        self.order.iter().map(String::as_str)
+    }
+
+    /// Service-wide settings
+    pub fn settings(&self) -> &ServiceSettings {
+        &self.settings
+    }
+}
+
+impl ServiceRegistry<InMemoryStorage> {
+    /// Build a registry backed by in-memory storage for every resource
+    pub fn in_memory(definition: ServiceDefinition) -> Result<Self, MetaRestError> {
+        Self::from_definition(definition, |_| InMemoryStorage::new())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CallerContext, FieldType, Resource};
+
+    const SERVICE_JSON: &str = r#"{
+        "name": "shop",
+        "version": "1.0",
+        "settings": {
+            "base_path": "/api",
+            "default_security": { "require_auth": true }
+        },
+        "resources": [
+            {
+                "name": "products",
+                "fields": [
+                    { "name": "title", "field_type": "string", "required": true }
+                ]
+            },
+            {
+                "name": "orders",
+                "fields": [
+                    { "name": "total", "field_type": "number", "required": true }
+                ],
+                "security": { "require_auth": false }
+            }
+        ]
+    }"#;
+
+    #[test]
+    fn test_registry_from_json() {
+        let registry =
+            ServiceRegistry::from_json(SERVICE_JSON, |_| InMemoryStorage::new()).unwrap();
+
+        let names: Vec<&str> = registry.resource_names().collect();
+        assert_eq!(names, vec!["products", "orders"]);
+        assert_eq!(registry.settings().base_path.as_deref(), Some("/api"));
+        assert!(registry.get("customers").is_none());
+
+        let security = |name| registry.get(name).unwrap().definition().security.clone();
+        assert!(security("products").unwrap().require_auth);
+        assert!(!security("orders").unwrap().require_auth);
+    }
+
+    #[test]
+    fn test_registry_managers_are_independent() {
+        let definition = ServiceDefinition::from_json(SERVICE_JSON).unwrap();
+        let mut registry = ServiceRegistry::in_memory(definition).unwrap();
+        let caller = CallerContext::authenticated("alice", &[]);
+
+        let mut data = HashMap::new();
+        data.insert("title".to_string(), serde_json::json!("Lamp"));
+        registry
+            .get_mut("products")
+            .unwrap()
+            .create(
+                &caller,
+                Resource {
+                    id: "1".to_string(),
+                    data,
+                },
+            )
+            .unwrap();
+
+        assert_eq!(
+            registry
+                .get("products")
+                .unwrap()
+                .list(&caller)
+                .unwrap()
+                .len(),
+            1
+        );
+        assert!(registry
+            .get("orders")
+            .unwrap()
+            .list(&caller)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_duplicate_resource_names_rejected() {
+        let mut definition = ServiceDefinition::from_json(SERVICE_JSON).unwrap();
+        definition.resources.push(definition.resources[0].clone());
+
+        match ServiceRegistry::in_memory(definition) {
+            Err(MetaRestError::InvalidDefinition(msg)) => assert!(msg.contains("products")),
+            _ => panic!("Expected InvalidDefinition error"),
+        }
+    }
+
+    #[test]
+    fn test_registry_with_validators() {
+        let json = SERVICE_JSON.replace(
+            r#""required": true }"#,
+            r#""required": true, "validators": ["positive"] }"#,
+        );
+        let definition = ServiceDefinition::from_json(&json).unwrap();
+        match ServiceRegistry::in_memory(definition.clone()) {
+            Err(MetaRestError::InvalidDefinition(msg)) => assert!(msg.contains("'positive'")),
+            _ => panic!("Expected InvalidDefinition error"),
+        }
+
+        let mut options = ManagerOptions::default();
+        options
+            .validators
+            .register("positive", |value| match value.as_f64() {
+                Some(n) if n <= 0.0 => Err("must be positive".to_string()),
+                _ => Ok(()),
+            });
+        let mut registry =
+            ServiceRegistry::from_definition_with(definition, &options, |_| InMemoryStorage::new())
+                .unwrap();
+
+        let mut data = HashMap::new();
+        data.insert("total".to_string(), serde_json::json!(-5));
+        let result = registry.get_mut("orders").unwrap().create(
+            &CallerContext::anonymous(),
+            Resource {
+                id: "1".to_string(),
+                data,
+            },
+        );
+        assert!(matches!(result, Err(MetaRestError::ValidationError(_))));
+    }
+
+    #[test]
+    fn test_unknown_field_types_rejected() {
+        let json = SERVICE_JSON.replace(r#""field_type": "number""#, r#""field_type": "money""#);
+        let definition = ServiceDefinition::from_json(&json).unwrap();
+        match ServiceRegistry::in_memory(definition.clone()) {
+            Err(MetaRestError::InvalidDefinition(msg)) => {
+                assert!(msg.contains("unknown type 'money'"))
+            }
+            _ => panic!("Expected InvalidDefinition error"),
+        }
+
+        let mut options = ManagerOptions::default();
+        options
+            .types
+            .register("money", FieldType::new(|value| value.is_number()))
+            .unwrap();
+        let registry =
+            ServiceRegistry::from_definition_with(definition, &options, |_| InMemoryStorage::new());
+        assert!(registry.is_ok());
+    }
+
+    #[test]
+    fn test_shared_types_of_service() {
+        let mut definition = ServiceDefinition::from_json(SERVICE_JSON).unwrap();
+        definition.shared_types = serde_json::from_value(serde_json::json!({
+            "amount": { "field_type": "number", "validation": { "min": 0.0 } }
+        }))
+        .unwrap();
+        definition.resources[1].fields[0] =
+            serde_json::from_value(serde_json::json!({ "name": "total", "$ref": "amount" }))
+                .unwrap();
+        let registry = ServiceRegistry::in_memory(definition).unwrap();
+
+        let total = registry.get("orders").unwrap().definition().field("total");
+        assert_eq!(total.unwrap().field_type, "number");
+        assert!(total.unwrap().validation.is_some());
+    }
+
+    #[test]
+    fn test_resources_inherit_from_bases() {
+        let mut definition = ServiceDefinition::from_json(SERVICE_JSON).unwrap();
+        definition.bases = serde_json::from_value(serde_json::json!([{
+            "name": "owned",
+            "fields": [{ "name": "owner", "field_type": "string", "required": true }],
+            "security": { "require_auth": false }
+        }]))
+        .unwrap();
+        definition.resources[0].extends = Some("owned".to_string());
+        definition.resources[1].mixins = vec!["owned".to_string()];
+        let registry = ServiceRegistry::in_memory(definition.clone()).unwrap();
+
+        let names: Vec<&str> = registry.resource_names().collect();
+        assert_eq!(names, vec!["products", "orders"]);
+        for name in names {
+            let manager = registry.get(name).unwrap();
+            assert!(manager.definition().field("owner").is_some());
+            assert!(!manager.definition().security.clone().unwrap().require_auth);
+        }
+
+        definition.resources[1].mixins = vec!["audited".to_string()];
+        match ServiceRegistry::in_memory(definition) {
+            Err(MetaRestError::InvalidDefinition(msg)) => {
+                assert!(msg.contains("unknown definition 'audited'"))
+            }
+            _ => panic!("Expected InvalidDefinition error"),
+        }
+    }
+
+    #[test]
+    fn test_invalid_json_rejected() {
+        let result = ServiceDefinition::from_json(r#"{"name": "shop"}"#);
+        assert!(matches!(result, Err(MetaRestError::InvalidDefinition(_))));
+    }
+}
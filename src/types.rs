@@ -0,0 +1,230 @@
+//! Field types
+//!
+//! The built-in types `string`, `number`, `integer`, `decimal`, `boolean`, `array` and
+//! `object` are always available. Applications add their own types, such as `money` or
+//! `phone`, to a [`TypeRegistry`]; each has a type check, and optionally a normalization
+//! applied to input before validation and a comparison used by filters and sorting.
+//!
+//! Definitions are checked against the registry when a manager is created, so a typo such
+//! as `"strng"` is an error instead of a field that accepts anything.
+
+use crate::{Field, MetaRestError};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+
+/// Names of the built-in field types
+pub const BUILT_IN_TYPES: [&str; 7] = [
+    "string", "number", "integer", "decimal", "boolean", "array", "object",
+];
+
+/// Checks whether a value is of a field type
+pub type TypeCheck = Arc<dyn Fn(&serde_json::Value) -> bool + Send + Sync>;
+
+/// Brings a value of a field type into its canonical form
+pub type Normalizer = Arc<dyn Fn(serde_json::Value) -> serde_json::Value + Send + Sync>;
+
+/// Orders a stored value relative to another value
+///
+/// The order must be total, so that sorting is well-defined: values that have no natural
+/// order between them, such as amounts in different currencies, are ranked by a key of
+/// their own, such as the currency.
+pub type Comparator = Arc<dyn Fn(&serde_json::Value, &serde_json::Value) -> Ordering + Send + Sync>;
+
+/// Behavior of a custom field type
+#[derive(Clone)]
+pub struct FieldType {
+    check: TypeCheck,
+    normalize: Option<Normalizer>,
+    compare: Option<Comparator>,
+}
+
+impl FieldType {
+    /// Create a type whose values pass the given check
+    pub fn new<F>(check: F) -> Self
+    where
+        F: Fn(&serde_json::Value) -> bool + Send + Sync + 'static,
+    {
+        Self {
+            check: Arc::new(check),
+            normalize: None,
+            compare: None,
+        }
+    }
+
+    /// Set the normalization applied to input before validation
+    ///
+    /// It receives any value sent for the field, and should return values it does not
+    /// understand unchanged so that the type check reports them.
+    pub fn normalize<F>(mut self, normalize: F) -> Self
+    where
+        F: Fn(serde_json::Value) -> serde_json::Value + Send + Sync + 'static,
+    {
+        self.normalize = Some(Arc::new(normalize));
+        self
+    }
+
+    /// Set the comparison used by filters and sorting instead of the JSON value order
+    ///
+    /// It receives a stored value and a filter value or another stored value, and must be a
+    /// total order: values without a natural order between them, such as amounts in different
+    /// currencies, are ranked by a key of their own, such as the currency first.
+    pub fn compare<F>(mut self, compare: F) -> Self
+    where
+        F: Fn(&serde_json::Value, &serde_json::Value) -> Ordering + Send + Sync + 'static,
+    {
+        self.compare = Some(Arc::new(compare));
+        self
+    }
+
+    /// Whether a value is of this type
+    pub fn is_valid(&self, value: &serde_json::Value) -> bool {
+        (self.check)(value)
+    }
+}
+
+impl fmt::Debug for FieldType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("FieldType")
+            .field("normalize", &self.normalize.is_some())
+            .field("compare", &self.compare.is_some())
+            .finish()
+    }
+}
+
+/// Custom field types available to resource definitions, next to the built-in ones
+#[derive(Clone, Default)]
+pub struct TypeRegistry {
+    types: HashMap<String, FieldType>,
+}
+
+impl TypeRegistry {
+    /// Create a registry with only the built-in types
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a custom type
+    ///
+    /// Fails if the name is that of a built-in type or of a type registered before.
+    pub fn register(
+        &mut self,
+        name: impl Into<String>,
+        field_type: FieldType,
+    ) -> Result<(), MetaRestError> {
+        let name = name.into();
+        if BUILT_IN_TYPES.contains(&name.as_str()) || self.types.contains_key(&name) {
+            return Err(MetaRestError::InvalidDefinition(format!(
+                "Field type '{}' is already defined",
+                name
+            )));
+        }
+        self.types.insert(name, field_type);
+        Ok(())
+    }
+
+    /// Look up a custom type by name
+    pub fn get(&self, name: &str) -> Option<&FieldType> {
+        self.types.get(name)
+    }
+
+    /// Whether a type name is built in or registered
+    pub fn contains(&self, name: &str) -> bool {
+        BUILT_IN_TYPES.contains(&name) || self.types.contains_key(name)
+    }
+
+    /// Check that every field, nested field and item schema has a known type
+    pub(crate) fn check_fields(&self, fields: &[Field], prefix: &str) -> Result<(), MetaRestError> {
+        for field in fields {
+            let path = match prefix {
+                "" => field.name.clone(),
+                _ => format!("{}.{}", prefix, field.name),
+            };
+            self.check_field(field, &path)?;
+        }
+        Ok(())
+    }
+
+    fn check_field(&self, field: &Field, path: &str) -> Result<(), MetaRestError> {
+        if !self.contains(&field.field_type) {
+            return Err(MetaRestError::InvalidDefinition(format!(
+                "Field '{}' has unknown type '{}'",
+                path, field.field_type
+            )));
+        }
+        if let Some(nested) = &field.fields {
+            self.check_fields(nested, path)?;
+        }
+        if let Some(items) = &field.items {
+            self.check_field(items, &format!("{}[]", path))?;
+        }
+        Ok(())
+    }
+
+    /// Normalize a value of a field with the normalization of its custom type, if any
+    pub(crate) fn normalize(&self, field: &Field, value: &mut serde_json::Value) {
+        if let Some(normalize) = self
+            .get(&field.field_type)
+            .and_then(|t| t.normalize.as_ref())
+        {
+            *value = normalize(std::mem::take(value));
+        }
+    }
+
+    /// Comparators of fields with a custom comparison, by path of the field
+    ///
+    /// Array items are not included, since filters and sorting address fields by path.
+    pub(crate) fn comparators(
+        &self,
+        fields: &[Field],
+        prefix: &str,
+    ) -> HashMap<String, Comparator> {
+        let mut comparators = HashMap::new();
+        for field in fields {
+            let path = match prefix {
+                "" => field.name.clone(),
+                _ => format!("{}.{}", prefix, field.name),
+            };
+            if let Some(compare) = self.get(&field.field_type).and_then(|t| t.compare.clone()) {
+                comparators.insert(path.clone(), compare);
+            }
+            if let Some(nested) = &field.fields {
+                comparators.extend(self.comparators(nested, &path));
+            }
+        }
+        comparators
+    }
+}
+
+impl fmt::Debug for TypeRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut names: Vec<&String> = self.types.keys().collect();
+        names.sort();
+        f.debug_struct("TypeRegistry")
+            .field("types", &names)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_registry() {
+        let mut types = TypeRegistry::new();
+        assert!(types.contains("decimal"));
+        assert!(!types.contains("phone"));
+
+        let phone = FieldType::new(|value| value.as_str().is_some_and(|s| s.starts_with('+')));
+        types.register("phone", phone.clone()).unwrap();
+        assert!(types.contains("phone"));
+        assert!(types.get("phone").unwrap().is_valid(&json!("+4930123")));
+        assert!(!types.get("phone").unwrap().is_valid(&json!(4930123)));
+
+        assert!(types.register("phone", phone.clone()).is_err());
+        assert!(types.register("string", phone).is_err());
+    }
+}
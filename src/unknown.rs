@@ -0,0 +1,107 @@
+//! Handling of fields that are not part of a definition
+//!
+//! The policy applies to the top level of a resource and to every nested object whose
+//! fields are declared, including objects inside arrays.
+
+use crate::{Field, RuleKind, ValidationErrors, Violation};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// What to do with fields that are not declared by the definition
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UnknownFields {
+    /// Store undeclared fields as they are
+    #[default]
+    Allow,
+    /// Fail validation with a violation per undeclared field
+    Reject,
+    /// Silently drop undeclared fields before validation
+    Strip,
+}
+
+impl UnknownFields {
+    /// Whether undeclared fields are allowed
+    pub fn is_allow(&self) -> bool {
+        *self == UnknownFields::Allow
+    }
+}
+
+fn is_declared(fields: &[Field], name: &str) -> bool {
+    fields.iter().any(|field| field.name == name)
+}
+
+/// Record a violation for every undeclared field
+pub(crate) fn check_unknown_fields(
+    fields: &[Field],
+    data: &HashMap<String, serde_json::Value>,
+    errors: &mut ValidationErrors,
+) {
+    let mut names: Vec<&String> = data.keys().collect();
+    names.sort();
+    for name in names {
+        match fields.iter().find(|field| &field.name == name) {
+            Some(field) => check_value(field, name, &data[name], errors),
+            None => errors.push(unknown(name)),
+        }
+    }
+}
+
+fn check_value(
+    field: &Field,
+    path: &str,
+    value: &serde_json::Value,
+    errors: &mut ValidationErrors,
+) {
+    if let (Some(nested), Some(object)) = (&field.fields, value.as_object()) {
+        for (name, value) in object {
+            let path = format!("{}.{}", path, name);
+            match nested.iter().find(|field| &field.name == name) {
+                Some(field) => check_value(field, &path, value, errors),
+                None => errors.push(unknown(&path)),
+            }
+        }
+    }
+    if let (Some(items), Some(array)) = (&field.items, value.as_array()) {
+        for (index, item) in array.iter().enumerate() {
+            check_value(items, &format!("{}[{}]", path, index), item, errors);
+        }
+    }
+}
+
+fn unknown(path: &str) -> Violation {
+    Violation::new(
+        path,
+        RuleKind::Unknown,
+        format!("Field '{}' is not part of the definition", path),
+    )
+}
+
+/// Remove every undeclared field
+pub(crate) fn strip_unknown_fields(
+    fields: &[Field],
+    data: &mut HashMap<String, serde_json::Value>,
+) {
+    data.retain(|name, _| is_declared(fields, name));
+    for field in fields {
+        if let Some(value) = data.get_mut(&field.name) {
+            strip_value(field, value);
+        }
+    }
+}
+
+fn strip_value(field: &Field, value: &mut serde_json::Value) {
+    if let (Some(nested), Some(object)) = (&field.fields, value.as_object_mut()) {
+        object.retain(|name, _| is_declared(nested, name));
+        for field in nested {
+            if let Some(value) = object.get_mut(&field.name) {
+                strip_value(field, value);
+            }
+        }
+    }
+    if let (Some(items), Some(array)) = (&field.items, value.as_array_mut()) {
+        for item in array {
+            strip_value(items, item);
+        }
+    }
+}
@@ -0,0 +1,718 @@
+//! Structured validation failures
+//!
+//! Validation collects every failing field instead of stopping at the first problem. The
+//! resulting [`ValidationErrors`] serializes to JSON so it can be returned to API clients.
+
+use crate::decimal::Decimal;
+use crate::filter::values_equal;
+use crate::{Field, MetaRestError, TypeRegistry, ValidationRule, ValidatorRegistry};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fmt;
+
+/// Kind of rule a value violated
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RuleKind {
+    /// A required field is missing
+    Required,
+    /// The value has the wrong type
+    Type,
+    /// The value is null but the field is not nullable
+    Null,
+    /// The value, its length or its number of items is below the minimum
+    Min,
+    /// The value, its length or its number of items is above the maximum
+    Max,
+    /// The value does not match the pattern
+    Pattern,
+    /// An array item is a duplicate of an earlier one
+    Unique,
+    /// The value is not one of the allowed values
+    Enum,
+    /// The value is not valid in the declared format
+    Format,
+    /// The value has more digits than the scale or precision allows
+    Precision,
+    /// The value is not a multiple of the required step
+    MultipleOf,
+    /// The field is read-only and cannot be set by clients
+    ReadOnly,
+    /// The field is write-only and cannot be queried
+    WriteOnly,
+    /// The field cannot be changed after creation
+    Immutable,
+    /// The field is not part of the definition
+    Unknown,
+    /// The value does not compare to another field as a resource rule requires
+    Compare,
+    /// The field is set together with a field it excludes
+    Exclusive,
+    /// A custom validator rejected the value
+    Custom,
+    /// The value is malformed in some other way
+    Invalid,
+}
+
+/// A single validation failure
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Violation {
+    /// Path of the offending field
+    pub field: String,
+    /// Rule that was violated
+    pub rule: RuleKind,
+    /// What the rule expected
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expected: Option<serde_json::Value>,
+    /// What was actually found
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub actual: Option<serde_json::Value>,
+    /// Human readable description
+    pub message: String,
+}
+
+impl Violation {
+    /// Create a violation without expected/actual details
+    pub fn new(field: impl Into<String>, rule: RuleKind, message: impl Into<String>) -> Self {
+        Self {
+            field: field.into(),
+            rule,
+            expected: None,
+            actual: None,
+            message: message.into(),
+        }
+    }
+
+    /// Attach the expected and actual values
+    pub fn with_values(
+        mut self,
+        expected: impl Into<serde_json::Value>,
+        actual: impl Into<serde_json::Value>,
+    ) -> Self {
+        self.expected = Some(expected.into());
+        self.actual = Some(actual.into());
+        self
+    }
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+/// All validation failures of a resource
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ValidationErrors {
+    /// Individual failures, in field order
+    pub violations: Vec<Violation>,
+}
+
+impl ValidationErrors {
+    /// Whether no violations were recorded
+    pub fn is_empty(&self) -> bool {
+        self.violations.is_empty()
+    }
+
+    /// Record a violation
+    pub fn push(&mut self, violation: Violation) {
+        self.violations.push(violation);
+    }
+
+    /// Violations of a given field
+    pub fn for_field<'a>(&'a self, field: &'a str) -> impl Iterator<Item = &'a Violation> {
+        self.violations.iter().filter(move |v| v.field == field)
+    }
+}
+
+impl From<Violation> for ValidationErrors {
+    fn from(violation: Violation) -> Self {
+        Self {
+            violations: vec![violation],
+        }
+    }
+}
+
+impl fmt::Display for ValidationErrors {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let messages: Vec<&str> = self.violations.iter().map(|v| v.message.as_str()).collect();
+        write!(f, "{}", messages.join("; "))
+    }
+}
+
+/// Compile the validation patterns of fields and their nested fields, keyed by path
+///
+/// Patterns of array items are keyed by the array path followed by `[]`.
+pub(crate) fn compile_patterns(
+    fields: &[Field],
+    prefix: &str,
+    patterns: &mut HashMap<String, Regex>,
+) -> Result<(), MetaRestError> {
+    for field in fields {
+        compile_field(field, &join_path(prefix, &field.name), patterns)?;
+    }
+    Ok(())
+}
+
+fn compile_field(
+    field: &Field,
+    path: &str,
+    patterns: &mut HashMap<String, Regex>,
+) -> Result<(), MetaRestError> {
+    if let Some(pattern) = field.validation.as_ref().and_then(|v| v.pattern.as_ref()) {
+        let regex = Regex::new(pattern).map_err(|e| {
+            MetaRestError::InvalidDefinition(format!(
+                "Field '{}' has invalid pattern '{}': {}",
+                path, pattern, e
+            ))
+        })?;
+        patterns.insert(path.to_string(), regex);
+    }
+    if let Some(format) = field.validation.as_ref().and_then(|v| v.format) {
+        if field.field_type != "string" {
+            return Err(MetaRestError::InvalidDefinition(format!(
+                "Field '{}' has format '{}' but is of type '{}'",
+                path, format, field.field_type
+            )));
+        }
+    }
+    if let Some(rules) = &field.validation {
+        let numeric = matches!(field.field_type.as_str(), "number" | "integer" | "decimal");
+        if (rules.scale.is_some() || rules.precision.is_some()) && field.field_type != "decimal" {
+            return Err(MetaRestError::InvalidDefinition(format!(
+                "Field '{}' has a scale or precision but is of type '{}'",
+                path, field.field_type
+            )));
+        }
+        if let (Some(scale), Some(precision)) = (rules.scale, rules.precision) {
+            if scale > precision {
+                return Err(MetaRestError::InvalidDefinition(format!(
+                    "Field '{}' has scale {} greater than precision {}",
+                    path, scale, precision
+                )));
+            }
+        }
+        if let Some(step) = rules.multiple_of {
+            if !numeric || step <= 0.0 || !step.is_finite() {
+                return Err(MetaRestError::InvalidDefinition(format!(
+                    "Field '{}' of type '{}' cannot be a multiple of {}",
+                    path, field.field_type, step
+                )));
+            }
+        }
+    }
+    if field.allowed_values.as_ref().is_some_and(Vec::is_empty) {
+        return Err(MetaRestError::InvalidDefinition(format!(
+            "Field '{}' has an empty list of allowed values",
+            path
+        )));
+    }
+    if let Some(nested) = &field.fields {
+        if field.field_type != "object" {
+            return Err(MetaRestError::InvalidDefinition(format!(
+                "Field '{}' has nested fields but is of type '{}'",
+                path, field.field_type
+            )));
+        }
+        compile_patterns(nested, path, patterns)?;
+    }
+    if let Some(items) = &field.items {
+        if field.field_type != "array" {
+            return Err(MetaRestError::InvalidDefinition(format!(
+                "Field '{}' has an item schema but is of type '{}'",
+                path, field.field_type
+            )));
+        }
+        compile_field(items, &format!("{}[]", path), patterns)?;
+    }
+    Ok(())
+}
+
+/// Validate the fields of an object, recording every violation
+///
+/// `prefix` is the path of the enclosing object, empty at the top level.
+pub(crate) fn validate_fields<'a>(
+    fields: &[Field],
+    prefix: &str,
+    lookup: impl Fn(&str) -> Option<&'a serde_json::Value>,
+    patterns: &HashMap<String, Regex>,
+    validators: &ValidatorRegistry,
+    types: &TypeRegistry,
+    errors: &mut ValidationErrors,
+) {
+    for field in fields {
+        let path = join_path(prefix, &field.name);
+        match lookup(&field.name) {
+            Some(value) => validate_field(field, &path, value, patterns, validators, types, errors),
+            None if field.required == Some(true) => errors.push(Violation::new(
+                &path,
+                RuleKind::Required,
+                format!("Required field '{}' is missing", path),
+            )),
+            None => {}
+        }
+    }
+}
+
+/// Validate a single field value, recording every violation
+pub(crate) fn validate_field(
+    field: &Field,
+    path: &str,
+    value: &serde_json::Value,
+    patterns: &HashMap<String, Regex>,
+    validators: &ValidatorRegistry,
+    types: &TypeRegistry,
+    errors: &mut ValidationErrors,
+) {
+    // Null is a value of nullable fields only, and has no further rules to check
+    if value.is_null() {
+        if field.nullable != Some(true) {
+            errors.push(
+                Violation::new(
+                    path,
+                    RuleKind::Null,
+                    format!("Field '{}' cannot be null", path),
+                )
+                .with_values(field.field_type.as_str(), "null"),
+            );
+        }
+        return;
+    }
+
+    // Type checking
+    let valid_type = match field.field_type.as_str() {
+        "string" => value.is_string(),
+        "number" | "decimal" => value.is_number(),
+        "integer" => is_integer(value),
+        "boolean" => value.is_boolean(),
+        "array" => value.is_array(),
+        "object" => value.is_object(),
+        // Definitions with unknown types are rejected when the manager is created
+        custom => types.get(custom).is_none_or(|t| t.is_valid(value)),
+    };
+
+    if !valid_type {
+        errors.push(
+            Violation::new(
+                path,
+                RuleKind::Type,
+                format!(
+                    "Field '{}' has invalid type, expected '{}'",
+                    path, field.field_type
+                ),
+            )
+            .with_values(field.field_type.as_str(), json_type(value)),
+        );
+        return;
+    }
+
+    if let Some(allowed) = &field.allowed_values {
+        if !allowed.iter().any(|a| values_equal(a, value)) {
+            errors.push(
+                Violation::new(
+                    path,
+                    RuleKind::Enum,
+                    format!("Field '{}' value {} is not an allowed value", path, value),
+                )
+                .with_values(allowed.clone(), value.clone()),
+            );
+        }
+    }
+
+    // Validation rules
+    if let Some(rules) = &field.validation {
+        if let Some(min) = rules.min {
+            if field.field_type == "number" {
+                if let Some(num) = value.as_f64() {
+                    if num < min {
+                        errors.push(
+                            Violation::new(
+                                path,
+                                RuleKind::Min,
+                                format!(
+                                    "Field '{}' value {} is less than minimum {}",
+                                    path, num, min
+                                ),
+                            )
+                            .with_values(min, value.clone()),
+                        );
+                    }
+                }
+            } else if field.field_type == "string" {
+                if let Some(s) = value.as_str() {
+                    if s.len() < min as usize {
+                        errors.push(
+                            Violation::new(
+                                path,
+                                RuleKind::Min,
+                                format!("Field '{}' length is less than minimum {}", path, min),
+                            )
+                            .with_values(min, s.len()),
+                        );
+                    }
+                }
+            }
+        }
+
+        if let Some(max) = rules.max {
+            if field.field_type == "number" {
+                if let Some(num) = value.as_f64() {
+                    if num > max {
+                        errors.push(
+                            Violation::new(
+                                path,
+                                RuleKind::Max,
+                                format!(
+                                    "Field '{}' value {} is greater than maximum {}",
+                                    path, num, max
+                                ),
+                            )
+                            .with_values(max, value.clone()),
+                        );
+                    }
+                }
+            } else if field.field_type == "string" {
+                if let Some(s) = value.as_str() {
+                    if s.len() > max as usize {
+                        errors.push(
+                            Violation::new(
+                                path,
+                                RuleKind::Max,
+                                format!("Field '{}' length is greater than maximum {}", path, max),
+                            )
+                            .with_values(max, s.len()),
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    if let (Some(rules), serde_json::Value::Number(number)) = (&field.validation, value) {
+        validate_number(field, rules, path, number, errors);
+    }
+
+    if let (Some(rules), Some(items)) = (&field.validation, value.as_array()) {
+        validate_items(rules, path, items, errors);
+    }
+
+    if let (Some(format), Some(s)) = (
+        field.validation.as_ref().and_then(|v| v.format),
+        value.as_str(),
+    ) {
+        if !format.is_valid(s) {
+            errors.push(
+                Violation::new(
+                    path,
+                    RuleKind::Format,
+                    format!("Field '{}' is not a valid {}", path, format),
+                )
+                .with_values(format.as_str(), s),
+            );
+        }
+    }
+
+    if let (Some(regex), Some(s)) = (patterns.get(&schema_path(path)), value.as_str()) {
+        if !regex.is_match(s) {
+            errors.push(
+                Violation::new(
+                    path,
+                    RuleKind::Pattern,
+                    format!(
+                        "Field '{}' does not match pattern '{}'",
+                        path,
+                        regex.as_str()
+                    ),
+                )
+                .with_values(regex.as_str(), s),
+            );
+        }
+    }
+
+    validators.validate(field, path, value, errors);
+
+    if let (Some(nested), Some(object)) = (&field.fields, value.as_object()) {
+        validate_fields(
+            nested,
+            path,
+            |name| object.get(name),
+            patterns,
+            validators,
+            types,
+            errors,
+        );
+    }
+
+    if let (Some(schema), Some(items)) = (&field.items, value.as_array()) {
+        for (index, item) in items.iter().enumerate() {
+            validate_field(
+                schema,
+                &format!("{}[{}]", path, index),
+                item,
+                patterns,
+                validators,
+                types,
+                errors,
+            );
+        }
+    }
+}
+
+/// Check the exact rules of `integer` and `decimal` values, and the step of any number
+///
+/// Values are compared as decimals, so no floating point rounding is involved.
+fn validate_number(
+    field: &Field,
+    rules: &ValidationRule,
+    path: &str,
+    number: &serde_json::Number,
+    errors: &mut ValidationErrors,
+) {
+    let decimal = match Decimal::from_number(number) {
+        Some(decimal) => decimal,
+        None => return,
+    };
+    let exact = |bound: f64| Decimal::from_f64(bound).and_then(|b| decimal.compare(&b));
+
+    if field.field_type != "number" {
+        if let Some(min) = rules.min.filter(|&min| exact(min) == Some(Ordering::Less)) {
+            errors.push(
+                Violation::new(
+                    path,
+                    RuleKind::Min,
+                    format!(
+                        "Field '{}' value {} is less than minimum {}",
+                        path, number, min
+                    ),
+                )
+                .with_values(min, number.clone()),
+            );
+        }
+
+        if let Some(max) = rules
+            .max
+            .filter(|&max| exact(max) == Some(Ordering::Greater))
+        {
+            errors.push(
+                Violation::new(
+                    path,
+                    RuleKind::Max,
+                    format!(
+                        "Field '{}' value {} is greater than maximum {}",
+                        path, number, max
+                    ),
+                )
+                .with_values(max, number.clone()),
+            );
+        }
+    }
+
+    if let Some(scale) = rules.scale.filter(|&scale| decimal.scale() > scale) {
+        errors.push(
+            Violation::new(
+                path,
+                RuleKind::Precision,
+                format!(
+                    "Field '{}' value {} has more than {} decimal places",
+                    path, number, scale
+                ),
+            )
+            .with_values(scale, decimal.scale()),
+        );
+    }
+
+    if let Some(precision) = rules.precision {
+        // As in SQL DECIMAL(precision, scale), the scale reserves fractional digits
+        let (allowed, digits) = match rules.scale {
+            Some(scale) => (precision.saturating_sub(scale), decimal.integer_digits()),
+            None => (precision, decimal.integer_digits() + decimal.scale()),
+        };
+        if digits > allowed {
+            errors.push(
+                Violation::new(
+                    path,
+                    RuleKind::Precision,
+                    format!(
+                        "Field '{}' value {} exceeds precision {}",
+                        path, number, precision
+                    ),
+                )
+                .with_values(precision, number.clone()),
+            );
+        }
+    }
+
+    if let Some(step) = rules.multiple_of {
+        let multiple = Decimal::from_f64(step).and_then(|step| decimal.is_multiple_of(&step));
+        if multiple == Some(false) {
+            errors.push(
+                Violation::new(
+                    path,
+                    RuleKind::MultipleOf,
+                    format!(
+                        "Field '{}' value {} is not a multiple of {}",
+                        path, number, step
+                    ),
+                )
+                .with_values(step, number.clone()),
+            );
+        }
+    }
+}
+
+/// Check the item count and uniqueness rules of an array
+fn validate_items(
+    rules: &ValidationRule,
+    path: &str,
+    items: &[serde_json::Value],
+    errors: &mut ValidationErrors,
+) {
+    if let Some(min) = rules.min_items.filter(|&min| items.len() < min) {
+        errors.push(
+            Violation::new(
+                path,
+                RuleKind::Min,
+                format!("Field '{}' has fewer than {} items", path, min),
+            )
+            .with_values(min, items.len()),
+        );
+    }
+
+    if let Some(max) = rules.max_items.filter(|&max| items.len() > max) {
+        errors.push(
+            Violation::new(
+                path,
+                RuleKind::Max,
+                format!("Field '{}' has more than {} items", path, max),
+            )
+            .with_values(max, items.len()),
+        );
+    }
+
+    if rules.unique_items == Some(true) {
+        for (index, item) in items.iter().enumerate() {
+            if items[..index]
+                .iter()
+                .any(|earlier| values_equal(earlier, item))
+            {
+                let item_path = format!("{}[{}]", path, index);
+                errors.push(Violation::new(
+                    &item_path,
+                    RuleKind::Unique,
+                    format!("Field '{}' duplicates an earlier item", item_path),
+                ));
+            }
+        }
+    }
+}
+
+/// Path of the schema a value path refers to, with array indexes replaced by `[]`
+fn schema_path(path: &str) -> String {
+    let mut schema = String::with_capacity(path.len());
+    let mut in_index = false;
+    for c in path.chars() {
+        match c {
+            '[' => {
+                in_index = true;
+                schema.push_str("[]");
+            }
+            ']' => in_index = false,
+            _ if !in_index => schema.push(c),
+            _ => {}
+        }
+    }
+    schema
+}
+
+/// Path of a field within an object at `prefix`
+fn join_path(prefix: &str, name: &str) -> String {
+    if prefix.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}.{}", prefix, name)
+    }
+}
+
+/// Name of the JSON type of a value
+pub(crate) fn json_type(value: &serde_json::Value) -> &'static str {
+    match value {
+        serde_json::Value::Null => "null",
+        serde_json::Value::Bool(_) => "boolean",
+        serde_json::Value::Number(_) => "number",
+        serde_json::Value::String(_) => "string",
+        serde_json::Value::Array(_) => "array",
+        serde_json::Value::Object(_) => "object",
+    }
+}
+
+/// Whether a value is a whole number: an integer, or a floating point number such as `3.0`
+/// with no fractional part that fits in an `i64`
+fn is_integer(value: &serde_json::Value) -> bool {
+    if value.is_i64() || value.is_u64() {
+        return true;
+    }
+    let range = -(2f64.powi(63))..2f64.powi(63);
+    value
+        .as_f64()
+        .is_some_and(|n| n.is_finite() && n.fract() == 0.0 && range.contains(&n))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_whole_floats_are_integers() {
+        assert!(is_integer(&json!(3)) && is_integer(&json!(u64::MAX)));
+        assert!(is_integer(&json!(3.0)) && is_integer(&json!(-2e18)));
+        assert!(!is_integer(&json!(3.5)));
+        assert!(!is_integer(&json!(1e19)));
+        assert!(!is_integer(&json!("3")));
+    }
+
+    #[test]
+    fn test_validation_errors_serialize_as_json() {
+        let mut errors = ValidationErrors::default();
+        errors.push(Violation::new(
+            "email",
+            RuleKind::Required,
+            "Required field 'email' is missing",
+        ));
+        errors.push(
+            Violation::new(
+                "age",
+                RuleKind::Max,
+                "Field 'age' value 200 is greater than maximum 150",
+            )
+            .with_values(150.0, 200),
+        );
+
+        let json = serde_json::to_value(&errors).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "violations": [
+                    {
+                        "field": "email",
+                        "rule": "required",
+                        "message": "Required field 'email' is missing"
+                    },
+                    {
+                        "field": "age",
+                        "rule": "max",
+                        "expected": 150.0,
+                        "actual": 200,
+                        "message": "Field 'age' value 200 is greater than maximum 150"
+                    }
+                ]
+            })
+        );
+        assert_eq!(
+            errors.to_string(),
+            "Required field 'email' is missing; Field 'age' value 200 is greater than maximum 150"
+        );
+    }
+}
@@ -0,0 +1,196 @@
+//! Custom validators written in Rust
+//!
+//! Checks that cannot be expressed declaratively, such as IBAN checksums or lookups in a
+//! reference table, are registered by name in a [`ValidatorRegistry`] and referenced from
+//! fields of a meta-description:
+//!
+//! ```json
+//! { "name": "iban", "field_type": "string", "validators": ["iban"] }
+//! ```
+//!
+//! Validators run after the built-in rules of a field, and only on values of the right type.
+
+use crate::{Field, MetaRestError, RuleKind, ValidationErrors, Violation};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+
+/// Checks a field value, returning a description of the problem if it is invalid
+pub type Validator = Arc<dyn Fn(&serde_json::Value) -> Result<(), String> + Send + Sync>;
+
+/// Named custom validators available to resource definitions
+#[derive(Clone, Default)]
+pub struct ValidatorRegistry {
+    validators: HashMap<String, Validator>,
+}
+
+impl ValidatorRegistry {
+    /// Create an empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a validator under a name, replacing any validator of the same name
+    pub fn register<F>(&mut self, name: impl Into<String>, validator: F)
+    where
+        F: Fn(&serde_json::Value) -> Result<(), String> + Send + Sync + 'static,
+    {
+        self.validators.insert(name.into(), Arc::new(validator));
+    }
+
+    /// Look up a validator by name
+    pub fn get(&self, name: &str) -> Option<&Validator> {
+        self.validators.get(name)
+    }
+
+    /// Check that every validator referenced by the fields is registered
+    pub(crate) fn check_fields(&self, fields: &[Field], prefix: &str) -> Result<(), MetaRestError> {
+        for field in fields {
+            let path = match prefix {
+                "" => field.name.clone(),
+                _ => format!("{}.{}", prefix, field.name),
+            };
+            self.check_field(field, &path)?;
+        }
+        Ok(())
+    }
+
+    fn check_field(&self, field: &Field, path: &str) -> Result<(), MetaRestError> {
+        if let Some(name) = field
+            .validators
+            .iter()
+            .flatten()
+            .find(|name| self.get(name).is_none())
+        {
+            return Err(MetaRestError::InvalidDefinition(format!(
+                "Field '{}' refers to unregistered validator '{}'",
+                path, name
+            )));
+        }
+        if let Some(nested) = &field.fields {
+            self.check_fields(nested, path)?;
+        }
+        if let Some(items) = &field.items {
+            self.check_field(items, &format!("{}[]", path))?;
+        }
+        Ok(())
+    }
+
+    /// Run the validators of a field on a value, recording a violation for each failure
+    pub(crate) fn validate(
+        &self,
+        field: &Field,
+        path: &str,
+        value: &serde_json::Value,
+        errors: &mut ValidationErrors,
+    ) {
+        for name in field.validators.iter().flatten() {
+            let result = match self.get(name) {
+                Some(validator) => validator(value),
+                None => Err("validator is not registered".to_string()),
+            };
+            if let Err(message) = result {
+                errors.push(
+                    Violation::new(
+                        path,
+                        RuleKind::Custom,
+                        format!("Field '{}' failed validator '{}': {}", path, name, message),
+                    )
+                    .with_values(name.as_str(), value.clone()),
+                );
+            }
+        }
+    }
+}
+
+impl fmt::Debug for ValidatorRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut names: Vec<&String> = self.validators.keys().collect();
+        names.sort();
+        f.debug_struct("ValidatorRegistry")
+            .field("validators", &names)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn registry() -> ValidatorRegistry {
+        let mut validators = ValidatorRegistry::new();
+        validators.register("even", |value| match value.as_i64() {
+            Some(n) if n % 2 == 0 => Ok(()),
+            _ => Err("must be even".to_string()),
+        });
+        validators
+    }
+
+    fn field(field: serde_json::Value) -> Field {
+        serde_json::from_value(field).unwrap()
+    }
+
+    #[test]
+    fn test_registry() {
+        let mut validators = registry();
+        assert!(validators.get("even").is_some());
+        assert!(validators.get("odd").is_none());
+
+        validators.register("even", |_| Err("replaced".to_string()));
+        assert_eq!(
+            validators.get("even").unwrap()(&json!(2)),
+            Err("replaced".to_string())
+        );
+        assert_eq!(
+            format!("{:?}", validators),
+            r#"ValidatorRegistry { validators: ["even"] }"#
+        );
+    }
+
+    #[test]
+    fn test_unregistered_validators_rejected() {
+        let validators = registry();
+        let lines = field(json!({
+            "name": "lines", "field_type": "array",
+            "items": { "field_type": "object", "fields": [
+                { "name": "quantity", "field_type": "integer", "validators": ["even", "odd"] }
+            ] }
+        }));
+
+        assert!(validators.check_fields(&[], "").is_ok());
+        match validators.check_fields(&[lines], "order") {
+            Err(MetaRestError::InvalidDefinition(msg)) => assert!(
+                msg.contains("'order.lines[].quantity' refers to unregistered validator 'odd'")
+            ),
+            _ => panic!("Expected InvalidDefinition error"),
+        }
+    }
+
+    #[test]
+    fn test_failing_validator_violation() {
+        let validators = registry();
+        let quantity = field(json!({
+            "name": "quantity", "field_type": "integer", "validators": ["even"]
+        }));
+
+        let mut errors = ValidationErrors::default();
+        validators.validate(&quantity, "quantity", &json!(4), &mut errors);
+        assert!(errors.is_empty());
+
+        validators.validate(&quantity, "quantity", &json!(3), &mut errors);
+        let violation = &errors.violations[0];
+        assert_eq!(
+            (violation.field.as_str(), violation.rule),
+            ("quantity", RuleKind::Custom)
+        );
+        assert_eq!(
+            violation.message,
+            "Field 'quantity' failed validator 'even': must be even"
+        );
+        assert_eq!(
+            (violation.expected.clone(), violation.actual.clone()),
+            (Some(json!("even")), Some(json!(3)))
+        );
+    }
+}